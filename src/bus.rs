@@ -0,0 +1,611 @@
+//! 基于 `send_plugin_command` 的插件间消息总线
+//!
+//! 每条消息是一行 ascii 文本, command identifier 是 topic 的 fnv1a-32:
+//!
+//! ```text
+//! vbus1 <kind> <from> <to> <id> <topic> <len> <payload>
+//! ```
+//!
+//! - kind: `E` 事件, `Q` 请求, `R` 响应
+//! - from / to: 节点名 (一般是插件名), 广播的时候 to 是 `*`
+//! - id: 请求和响应对应用的 id, 事件是 0
+//! - topic: `namespace.name` 形式, 只能有字母数字和 `_` `-`
+//! - len: payload 解码后的字节数
+//! - payload: base64 (标准字母表, 带 padding), 空的时候是 `-`
+//!
+//! 别的语言的插件照着这个格式收发就可以互通
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::time::Duration;
+
+use crate::events::PluginCommandEvent;
+use crate::events::server::ServerFrameEvent;
+use crate::func::PluginMethods;
+use crate::clock::FrameClock;
+use crate::{VcmpError, vcmp_func};
+
+const PROTOCOL: &str = "vbus1";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BusError {
+    /// topic 或者节点名不合法
+    InvalidName(String),
+    /// 发送失败
+    Vcmp(VcmpError),
+    /// 请求超时
+    Timeout,
+    /// payload 解不出来
+    Decode,
+}
+
+impl std::error::Error for BusError {}
+
+impl Display for BusError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BusError::InvalidName(name) => write!(f, "无效名称 {name}"),
+            BusError::Vcmp(err) => write!(f, "发送失败: {err}"),
+            BusError::Timeout => write!(f, "请求超时"),
+            BusError::Decode => write!(f, "payload 解码失败"),
+        }
+    }
+}
+
+impl From<VcmpError> for BusError {
+    fn from(value: VcmpError) -> Self {
+        BusError::Vcmp(value)
+    }
+}
+
+pub type BusResult<T> = Result<T, BusError>;
+
+/// 可以放进总线的数据
+pub trait BusPayload: Sized {
+    fn encode(&self) -> Vec<u8>;
+    fn decode(data: &[u8]) -> Option<Self>;
+}
+
+impl BusPayload for Vec<u8> {
+    fn encode(&self) -> Vec<u8> {
+        self.clone()
+    }
+    fn decode(data: &[u8]) -> Option<Self> {
+        Some(data.to_vec())
+    }
+}
+
+impl BusPayload for String {
+    fn encode(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+    fn decode(data: &[u8]) -> Option<Self> {
+        String::from_utf8(data.to_vec()).ok()
+    }
+}
+
+impl BusPayload for () {
+    fn encode(&self) -> Vec<u8> {
+        Vec::new()
+    }
+    fn decode(_: &[u8]) -> Option<Self> {
+        Some(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MessageKind {
+    Event,
+    Request,
+    Response,
+}
+
+impl MessageKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            MessageKind::Event => "E",
+            MessageKind::Request => "Q",
+            MessageKind::Response => "R",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "E" => Some(MessageKind::Event),
+            "Q" => Some(MessageKind::Request),
+            "R" => Some(MessageKind::Response),
+            _ => None,
+        }
+    }
+}
+
+/// 总线上的一条消息
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Envelope {
+    pub kind: MessageKind,
+    pub from: String,
+    /// None 表示广播
+    pub to: Option<String>,
+    pub id: u64,
+    pub topic: String,
+    pub payload: Vec<u8>,
+}
+
+impl Envelope {
+    /// 编码成发给 `send_plugin_command` 的文本
+    pub fn encode(&self) -> String {
+        let payload = if self.payload.is_empty() {
+            "-".to_string()
+        } else {
+            base64_encode(&self.payload)
+        };
+        format!(
+            "{PROTOCOL} {} {} {} {} {} {} {payload}",
+            self.kind.as_str(),
+            self.from,
+            self.to.as_deref().unwrap_or("*"),
+            self.id,
+            self.topic,
+            self.payload.len(),
+        )
+    }
+
+    /// 解析收到的文本, 不是总线消息或者格式不对的返回 None
+    pub fn decode(message: &str) -> Option<Self> {
+        let mut parts = message.trim_end_matches('\0').split(' ');
+        if parts.next()? != PROTOCOL {
+            return None;
+        }
+        let kind = MessageKind::parse(parts.next()?)?;
+        let from = parts.next().filter(|from| is_valid_node(from))?.to_string();
+        let to = match parts.next()? {
+            "*" => None,
+            to if is_valid_node(to) => Some(to.to_string()),
+            _ => return None,
+        };
+        let id = parts.next()?.parse().ok()?;
+        let topic = parts
+            .next()
+            .filter(|topic| is_valid_topic(topic))?
+            .to_string();
+        let len: usize = parts.next()?.parse().ok()?;
+        let payload = match parts.next()? {
+            "-" => Vec::new(),
+            data => base64_decode(data)?,
+        };
+        if parts.next().is_some() || payload.len() != len {
+            return None;
+        }
+        Some(Self {
+            kind,
+            from,
+            to,
+            id,
+            topic,
+            payload,
+        })
+    }
+
+    pub fn payload<T: BusPayload>(&self) -> BusResult<T> {
+        T::decode(&self.payload).ok_or(BusError::Decode)
+    }
+}
+
+/// topic 对应的 command identifier (fnv1a-32)
+pub fn topic_identifier(topic: &str) -> u32 {
+    topic.bytes().fold(0x811c_9dc5_u32, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x0100_0193)
+    })
+}
+
+fn is_valid_segment(segment: &str) -> bool {
+    !segment.is_empty()
+        && segment
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// 节点名: 一段, 字母数字和 `_` `-`
+pub fn is_valid_node(node: &str) -> bool {
+    is_valid_segment(node)
+}
+
+/// topic: 至少两段, 用 `.` 分开
+pub fn is_valid_topic(topic: &str) -> bool {
+    let mut segments = 0;
+    for segment in topic.split('.') {
+        if !is_valid_segment(segment) {
+            return false;
+        }
+        segments += 1;
+    }
+    segments >= 2
+}
+
+type Subscriber = Box<dyn FnMut(&Envelope) + Send>;
+type Handler = Box<dyn FnMut(&Envelope) -> Option<Vec<u8>> + Send>;
+type PendingCallback = Box<dyn FnOnce(BusResult<Envelope>) + Send>;
+
+struct PendingRequest {
+    deadline: Duration,
+    callback: PendingCallback,
+}
+
+/// 消息总线
+///
+/// 需要在 `PluginCommand` 和 `ServerFrame` 里调用对应的方法
+pub struct MessageBus {
+    node: String,
+    clock: FrameClock,
+    timeout: Duration,
+    next_id: u64,
+    subscribers: HashMap<String, Vec<Subscriber>>,
+    handlers: HashMap<String, Handler>,
+    pending: HashMap<u64, PendingRequest>,
+}
+
+impl MessageBus {
+    /// `node` 是自己在总线上的名字, 一般用插件名
+    pub fn new(node: &str) -> BusResult<Self> {
+        if !is_valid_node(node) {
+            return Err(BusError::InvalidName(node.to_string()));
+        }
+        Ok(Self {
+            node: node.to_string(),
+            clock: FrameClock::new(),
+            timeout: Duration::from_secs(5),
+            next_id: 1,
+            subscribers: HashMap::new(),
+            handlers: HashMap::new(),
+            pending: HashMap::new(),
+        })
+    }
+
+    pub fn node(&self) -> &str {
+        &self.node
+    }
+
+    /// 请求的超时时间, 默认 5 秒
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// 订阅一个 topic 的事件, payload 解码失败的消息会被跳过
+    pub fn subscribe<T: BusPayload + 'static>(
+        &mut self,
+        topic: &str,
+        mut callback: impl FnMut(&Envelope, T) + Send + 'static,
+    ) -> BusResult<()> {
+        check_topic(topic)?;
+        self.subscribers
+            .entry(topic.to_string())
+            .or_default()
+            .push(Box::new(move |envelope| {
+                if let Ok(payload) = envelope.payload::<T>() {
+                    callback(envelope, payload);
+                }
+            }));
+        Ok(())
+    }
+
+    pub fn unsubscribe_all(&mut self, topic: &str) {
+        self.subscribers.remove(topic);
+    }
+
+    /// 处理一个 topic 的请求, 返回 None 时不回复
+    ///
+    /// 同一个 topic 只能有一个处理函数, 后注册的会覆盖前面的
+    pub fn handle<Req: BusPayload + 'static, Resp: BusPayload + 'static>(
+        &mut self,
+        topic: &str,
+        mut handler: impl FnMut(&Envelope, Req) -> Option<Resp> + Send + 'static,
+    ) -> BusResult<()> {
+        check_topic(topic)?;
+        self.handlers.insert(
+            topic.to_string(),
+            Box::new(move |envelope| {
+                let request = envelope.payload::<Req>().ok()?;
+                handler(envelope, request).map(|response| response.encode())
+            }),
+        );
+        Ok(())
+    }
+
+    /// 广播一个事件
+    pub fn publish<T: BusPayload>(&mut self, topic: &str, payload: &T) -> BusResult<()> {
+        self.send(MessageKind::Event, None, 0, topic, payload.encode())
+    }
+
+    /// 发一个请求, 第一个回复 (或者超时) 会调用 callback
+    ///
+    /// `to` 为 None 时广播给所有节点
+    pub fn request<Req: BusPayload, Resp: BusPayload + 'static>(
+        &mut self,
+        to: Option<&str>,
+        topic: &str,
+        payload: &Req,
+        callback: impl FnOnce(BusResult<Resp>) + Send + 'static,
+    ) -> BusResult<u64> {
+        if let Some(to) = to.filter(|to| !is_valid_node(to)) {
+            return Err(BusError::InvalidName(to.to_string()));
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        self.send(
+            MessageKind::Request,
+            to.map(|to| to.to_string()),
+            id,
+            topic,
+            payload.encode(),
+        )?;
+        self.pending.insert(
+            id,
+            PendingRequest {
+                deadline: self.clock.now() + self.timeout,
+                callback: Box::new(move |result| {
+                    callback(result.and_then(|envelope| envelope.payload::<Resp>()))
+                }),
+            },
+        );
+        Ok(id)
+    }
+
+    /// 还在等回复的请求数量
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// 处理收到的插件命令, 是总线消息的话返回 true
+    pub fn on_plugin_command(&mut self, event: &PluginCommandEvent) -> bool {
+        let Some(envelope) = Envelope::decode(&event.message) else {
+            return false;
+        };
+        if event.identifer != topic_identifier(&envelope.topic) {
+            return false;
+        }
+        // 自己发的, 或者不是发给自己的
+        if envelope.from == self.node || envelope.to.as_ref().is_some_and(|to| *to != self.node) {
+            return true;
+        }
+        match envelope.kind {
+            MessageKind::Event => {
+                if let Some(subscribers) = self.subscribers.get_mut(&envelope.topic) {
+                    for subscriber in subscribers.iter_mut() {
+                        subscriber(&envelope);
+                    }
+                }
+            }
+            MessageKind::Request => {
+                let response = self
+                    .handlers
+                    .get_mut(&envelope.topic)
+                    .and_then(|handler| handler(&envelope));
+                if let Some(response) = response {
+                    let _ = self.send(
+                        MessageKind::Response,
+                        Some(envelope.from.clone()),
+                        envelope.id,
+                        &envelope.topic,
+                        response,
+                    );
+                }
+            }
+            MessageKind::Response => {
+                if let Some(pending) = self.pending.remove(&envelope.id) {
+                    (pending.callback)(Ok(envelope));
+                }
+            }
+        }
+        true
+    }
+
+    /// 处理超时
+    pub fn on_server_frame(&mut self, event: &ServerFrameEvent) {
+        self.clock.advance(event);
+        let now = self.clock.now();
+        let expired: Vec<u64> = self
+            .pending
+            .iter()
+            .filter(|(_, pending)| pending.deadline <= now)
+            .map(|(id, _)| *id)
+            .collect();
+        for id in expired {
+            if let Some(pending) = self.pending.remove(&id) {
+                (pending.callback)(Err(BusError::Timeout));
+            }
+        }
+    }
+
+    fn send(
+        &self,
+        kind: MessageKind,
+        to: Option<String>,
+        id: u64,
+        topic: &str,
+        payload: Vec<u8>,
+    ) -> BusResult<()> {
+        check_topic(topic)?;
+        let envelope = Envelope {
+            kind,
+            from: self.node.clone(),
+            to,
+            id,
+            topic: topic.to_string(),
+            payload,
+        };
+        vcmp_func().send_plugin_command(topic_identifier(topic), &envelope.encode())?;
+        Ok(())
+    }
+}
+
+fn check_topic(topic: &str) -> BusResult<()> {
+    if is_valid_topic(topic) {
+        Ok(())
+    } else {
+        Err(BusError::InvalidName(topic.to_string()))
+    }
+}
+
+/*
+    base64
+*/
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = ((b[0] as u32) << 16) | ((b[1] as u32) << 8) | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64_ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn base64_decode(data: &str) -> Option<Vec<u8>> {
+    let bytes = data.as_bytes();
+    if !bytes.len().is_multiple_of(4) {
+        return None;
+    }
+    let mut out = Vec::with_capacity(bytes.len() / 4 * 3);
+    for (index, chunk) in bytes.chunks(4).enumerate() {
+        let is_last = index == bytes.len() / 4 - 1;
+        let padding = chunk.iter().rev().take_while(|c| **c == b'=').count();
+        if padding > 2 || (padding > 0 && !is_last) {
+            return None;
+        }
+        let mut n = 0u32;
+        for &c in &chunk[..4 - padding] {
+            let value = BASE64_ALPHABET.iter().position(|a| *a == c)? as u32;
+            n = (n << 6) | value;
+        }
+        n <<= 6 * padding as u32;
+        let decoded = [(n >> 16) as u8, (n >> 8) as u8, n as u8];
+        out.extend_from_slice(&decoded[..3 - padding]);
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn envelope(payload: &[u8]) -> Envelope {
+        Envelope {
+            kind: MessageKind::Request,
+            from: "lobby".to_string(),
+            to: Some("race-1".to_string()),
+            id: 42,
+            topic: "race.join".to_string(),
+            payload: payload.to_vec(),
+        }
+    }
+
+    #[test]
+    fn base64_known_values() {
+        let cases: [(&[u8], &str); 7] = [
+            (b"", ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"foob", "Zm9vYg=="),
+            (b"fooba", "Zm9vYmE="),
+            (b"foobar", "Zm9vYmFy"),
+        ];
+        for (raw, encoded) in cases {
+            assert_eq!(base64_encode(raw), encoded);
+            assert_eq!(base64_decode(encoded).as_deref(), Some(raw));
+        }
+        assert_eq!(base64_encode(&[0xFB, 0xFF]), "+/8=");
+    }
+
+    #[test]
+    fn base64_round_trip() {
+        let data: Vec<u8> = (0..=255).collect();
+        for len in 0..data.len() {
+            assert_eq!(
+                base64_decode(&base64_encode(&data[..len])).as_deref(),
+                Some(&data[..len])
+            );
+        }
+    }
+
+    #[test]
+    fn base64_rejects_malformed() {
+        for bad in ["Z", "Zg=", "Z===", "Zg==Zg==", "Zm9v!A==", "Zm=v"] {
+            assert_eq!(base64_decode(bad), None, "{bad}");
+        }
+    }
+
+    #[test]
+    fn envelope_encode() {
+        assert_eq!(
+            envelope(b"hi").encode(),
+            "vbus1 Q lobby race-1 42 race.join 2 aGk="
+        );
+        let broadcast = Envelope {
+            kind: MessageKind::Event,
+            to: None,
+            ..envelope(b"")
+        };
+        assert_eq!(broadcast.encode(), "vbus1 E lobby * 42 race.join 0 -");
+    }
+
+    #[test]
+    fn envelope_round_trip() {
+        for payload in [&b""[..], b"x", "中文 payload".as_bytes()] {
+            let message = envelope(payload);
+            assert_eq!(Envelope::decode(&message.encode()), Some(message));
+        }
+        // 服务器传过来的字符串可能带 \0
+        let message = envelope(b"abc");
+        assert_eq!(
+            Envelope::decode(&format!("{}\0", message.encode())),
+            Some(message)
+        );
+    }
+
+    #[test]
+    fn envelope_rejects_malformed() {
+        let bad = [
+            "",
+            "vbus2 Q lobby race-1 42 race.join 2 aGk=",
+            "vbus1 X lobby race-1 42 race.join 2 aGk=",
+            "vbus1 Q lo.bby race-1 42 race.join 2 aGk=",
+            "vbus1 Q lobby race-1 nope race.join 2 aGk=",
+            "vbus1 Q lobby race-1 42 race 2 aGk=",
+            "vbus1 Q lobby race-1 42 race.join 3 aGk=",
+            "vbus1 Q lobby race-1 42 race.join 2 aGk",
+            "vbus1 Q lobby race-1 42 race.join 2 aGk= extra",
+            "vbus1 Q lobby race-1 42 race.join 2",
+        ];
+        for message in bad {
+            assert_eq!(Envelope::decode(message), None, "{message}");
+        }
+    }
+
+    #[test]
+    fn topic_and_node_names() {
+        assert!(is_valid_topic("race.join"));
+        assert!(is_valid_topic("a.b-c.d_e"));
+        assert!(!is_valid_topic("race"));
+        assert!(!is_valid_topic("race..join"));
+        assert!(!is_valid_topic("race.join "));
+        assert!(is_valid_node("race-1"));
+        assert!(!is_valid_node("race.1"));
+        assert!(!is_valid_node(""));
+        assert_eq!(topic_identifier(""), 0x811c_9dc5);
+        assert_eq!(topic_identifier("a"), 0xe40c_292c);
+    }
+}
@@ -0,0 +1,19 @@
+//! 游戏内各种 "魔法数字" 的枚举
+//!
+//! 把 func 里面直接传 i32 的参数换成有名字的东西
+
+pub mod announce;
+pub mod blip;
+pub mod clock;
+pub mod explosion;
+pub mod key;
+pub mod sound;
+pub mod weather;
+
+pub use announce::AnnounceStyle;
+pub use blip::BlipSprite;
+pub use clock::GameClock;
+pub use explosion::ExplosionType;
+pub use key::KeyCode;
+pub use sound::Sound;
+pub use weather::Weather;
@@ -0,0 +1,63 @@
+/// 公告 (SendGameMessage) 的样式
+///
+/// 对应 `send_announce` 的 type 参数, 服务器只接受 0..=8
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum AnnounceStyle {
+    /// 屏幕下方的普通文本
+    #[default]
+    Normal, // 第一个变体，值=0
+    /// 任务标题样式的大字
+    MissionTitle,
+    /// 屏幕中央的大字 (类似 "MISSION PASSED")
+    BigMessage,
+    /// 字幕样式
+    Subtitle,
+    /// 右下角的区域名样式
+    ZoneName,
+    /// 右下角的载具名样式
+    VehicleName,
+    /// 金钱变动样式
+    Cash,
+    /// 左上角的寻呼机样式
+    Pager,
+    /// 左上角的帮助框样式
+    Help,
+}
+
+impl AnnounceStyle {
+    /// 所有样式
+    pub const ALL: [AnnounceStyle; 9] = [
+        Self::Normal,
+        Self::MissionTitle,
+        Self::BigMessage,
+        Self::Subtitle,
+        Self::ZoneName,
+        Self::VehicleName,
+        Self::Cash,
+        Self::Pager,
+        Self::Help,
+    ];
+}
+
+impl From<i32> for AnnounceStyle {
+    fn from(value: i32) -> Self {
+        match value {
+            0 => Self::Normal,
+            1 => Self::MissionTitle,
+            2 => Self::BigMessage,
+            3 => Self::Subtitle,
+            4 => Self::ZoneName,
+            5 => Self::VehicleName,
+            6 => Self::Cash,
+            7 => Self::Pager,
+            8 => Self::Help,
+            _ => Self::Normal, // 未知值转为第一个变体
+        }
+    }
+}
+
+impl From<AnnounceStyle> for i32 {
+    fn from(val: AnnounceStyle) -> Self {
+        val as i32
+    }
+}
@@ -0,0 +1,169 @@
+/// 小地图图标, 对应 `create_marker` 的 sprite
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum BlipSprite {
+    /// 普通的彩色圆点
+    #[default]
+    None, // 第一个变体，值=0
+    Centre,
+    MapHere,
+    North,
+    Avery,
+    Biker,
+    Cortez,
+    Diaz,
+    Kent,
+    Lawyer,
+    Phil,
+    Bikers,
+    Boatyard,
+    MalibuClub,
+    Cubans,
+    Film,
+    Gun,
+    Haitians,
+    Hardware,
+    SaveHouse,
+    StripClub,
+    Ice,
+    KaufmanCabs,
+    LoveFist,
+    PrintWorks,
+    Property,
+    SunYard,
+    Spray,
+    TShirt,
+    Tommy,
+    Phone,
+    RadioWildstyle,
+    RadioFlash,
+    RadioKChat,
+    RadioFever,
+    RadioVRock,
+    RadioVcpr,
+    RadioEspantoso,
+    RadioEmotion,
+    RadioWave,
+}
+
+impl BlipSprite {
+    /// 所有图标
+    pub const ALL: [BlipSprite; 40] = [
+        Self::None,
+        Self::Centre,
+        Self::MapHere,
+        Self::North,
+        Self::Avery,
+        Self::Biker,
+        Self::Cortez,
+        Self::Diaz,
+        Self::Kent,
+        Self::Lawyer,
+        Self::Phil,
+        Self::Bikers,
+        Self::Boatyard,
+        Self::MalibuClub,
+        Self::Cubans,
+        Self::Film,
+        Self::Gun,
+        Self::Haitians,
+        Self::Hardware,
+        Self::SaveHouse,
+        Self::StripClub,
+        Self::Ice,
+        Self::KaufmanCabs,
+        Self::LoveFist,
+        Self::PrintWorks,
+        Self::Property,
+        Self::SunYard,
+        Self::Spray,
+        Self::TShirt,
+        Self::Tommy,
+        Self::Phone,
+        Self::RadioWildstyle,
+        Self::RadioFlash,
+        Self::RadioKChat,
+        Self::RadioFever,
+        Self::RadioVRock,
+        Self::RadioVcpr,
+        Self::RadioEspantoso,
+        Self::RadioEmotion,
+        Self::RadioWave,
+    ];
+}
+
+impl From<i32> for BlipSprite {
+    fn from(value: i32) -> Self {
+        match value {
+            0 => Self::None,
+            1 => Self::Centre,
+            2 => Self::MapHere,
+            3 => Self::North,
+            4 => Self::Avery,
+            5 => Self::Biker,
+            6 => Self::Cortez,
+            7 => Self::Diaz,
+            8 => Self::Kent,
+            9 => Self::Lawyer,
+            10 => Self::Phil,
+            11 => Self::Bikers,
+            12 => Self::Boatyard,
+            13 => Self::MalibuClub,
+            14 => Self::Cubans,
+            15 => Self::Film,
+            16 => Self::Gun,
+            17 => Self::Haitians,
+            18 => Self::Hardware,
+            19 => Self::SaveHouse,
+            20 => Self::StripClub,
+            21 => Self::Ice,
+            22 => Self::KaufmanCabs,
+            23 => Self::LoveFist,
+            24 => Self::PrintWorks,
+            25 => Self::Property,
+            26 => Self::SunYard,
+            27 => Self::Spray,
+            28 => Self::TShirt,
+            29 => Self::Tommy,
+            30 => Self::Phone,
+            31 => Self::RadioWildstyle,
+            32 => Self::RadioFlash,
+            33 => Self::RadioKChat,
+            34 => Self::RadioFever,
+            35 => Self::RadioVRock,
+            36 => Self::RadioVcpr,
+            37 => Self::RadioEspantoso,
+            38 => Self::RadioEmotion,
+            39 => Self::RadioWave,
+            _ => Self::None, // 未知值转为第一个变体
+        }
+    }
+}
+
+impl From<BlipSprite> for i32 {
+    fn from(val: BlipSprite) -> Self {
+        val as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_sprite_id_is_its_index() {
+        // 最后一个变体的值也要在 ALL 里, 少写一个的话长度对不上
+        assert_eq!(BlipSprite::ALL.len(), BlipSprite::RadioWave as usize + 1);
+        for (index, sprite) in BlipSprite::ALL.into_iter().enumerate() {
+            assert_eq!(i32::from(sprite), index as i32);
+            assert_eq!(BlipSprite::from(index as i32), sprite);
+        }
+    }
+
+    #[test]
+    fn unknown_ids_fall_back_to_none() {
+        let count = BlipSprite::ALL.len() as i32;
+        for id in [-1, count, count + 1, 255, i32::MIN, i32::MAX] {
+            assert_eq!(BlipSprite::from(id), BlipSprite::None, "id {id}");
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use std::fmt::Display;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const MINUTES_PER_DAY: i32 = 24 * 60;
+
+/// 游戏内的时间 (一天之内的 时:分)
+///
+/// 和服务器的 time 值一样, 就是 `hour * 60 + minute`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct GameClock {
+    minutes: u16,
+}
+
+impl GameClock {
+    pub const MIDNIGHT: GameClock = GameClock { minutes: 0 };
+    pub const NOON: GameClock = GameClock { minutes: 12 * 60 };
+
+    /// 超出范围的时间会按一天绕回去
+    pub fn new(hour: i32, minute: i32) -> Self {
+        Self::wrap(hour as i64 * 60 + minute as i64)
+    }
+
+    /// 从一天中的第几分钟构造, 负数和超过一天的都会绕回去
+    pub fn from_minutes(minutes: i32) -> Self {
+        Self::wrap(minutes as i64)
+    }
+
+    /// 用 i64 算, 很大的 i32 乘 60 或者相加也不会溢出
+    fn wrap(minutes: i64) -> Self {
+        Self {
+            minutes: minutes.rem_euclid(MINUTES_PER_DAY as i64) as u16,
+        }
+    }
+
+    /// 按现实时间算, `utc_offset_minutes` 是时区相对 UTC 的分钟数
+    pub fn from_system_time(time: SystemTime, utc_offset_minutes: i32) -> Self {
+        let secs = time
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let minutes = (secs / 60 % MINUTES_PER_DAY as u64) as i64;
+        Self::wrap(minutes + utc_offset_minutes as i64)
+    }
+
+    pub fn hour(&self) -> u8 {
+        (self.minutes / 60) as u8
+    }
+
+    pub fn minute(&self) -> u8 {
+        (self.minutes % 60) as u8
+    }
+
+    /// 一天中的第几分钟
+    pub fn total_minutes(&self) -> u16 {
+        self.minutes
+    }
+
+    pub fn add_minutes(&self, minutes: i32) -> Self {
+        Self::wrap(self.minutes as i64 + minutes as i64)
+    }
+
+    pub fn add_hours(&self, hours: i32) -> Self {
+        Self::wrap(self.minutes as i64 + hours as i64 * 60)
+    }
+
+    /// 从现在往后走到 `other` 需要多少分钟
+    pub fn minutes_until(&self, other: GameClock) -> u16 {
+        (other.minutes as i32 - self.minutes as i32).rem_euclid(MINUTES_PER_DAY) as u16
+    }
+
+    /// 是否在 [start, end) 之间, 支持跨过午夜的区间
+    pub fn is_between(&self, start: GameClock, end: GameClock) -> bool {
+        if start <= end {
+            start <= *self && *self < end
+        } else {
+            *self >= start || *self < end
+        }
+    }
+}
+
+impl From<i32> for GameClock {
+    fn from(value: i32) -> Self {
+        Self::from_minutes(value)
+    }
+}
+
+impl From<GameClock> for i32 {
+    fn from(val: GameClock) -> Self {
+        val.minutes as i32
+    }
+}
+
+impl Display for GameClock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:02}:{:02}", self.hour(), self.minute())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wraps_around_the_day() {
+        assert_eq!(GameClock::new(25, 30), GameClock::new(1, 30));
+        assert_eq!(GameClock::new(0, -1), GameClock::new(23, 59));
+        assert_eq!(
+            GameClock::from_minutes(-MINUTES_PER_DAY),
+            GameClock::MIDNIGHT
+        );
+        assert_eq!(GameClock::new(23, 0).add_hours(2).to_string(), "01:00");
+        assert_eq!(GameClock::MIDNIGHT.add_minutes(-90).to_string(), "22:30");
+        assert_eq!(GameClock::NOON.hour(), 12);
+        assert_eq!(i32::from(GameClock::new(6, 15)), 375);
+    }
+
+    #[test]
+    fn large_inputs_do_not_overflow() {
+        assert_eq!(
+            GameClock::new(i32::MAX, i32::MAX).total_minutes() as i64,
+            (i32::MAX as i64 * 61).rem_euclid(MINUTES_PER_DAY as i64)
+        );
+        GameClock::new(i32::MIN, i32::MIN);
+        GameClock::NOON.add_minutes(i32::MAX);
+        GameClock::NOON.add_hours(i32::MIN);
+        GameClock::from_system_time(UNIX_EPOCH, i32::MAX);
+    }
+
+    #[test]
+    fn minutes_until_and_between() {
+        let evening = GameClock::new(20, 0);
+        let morning = GameClock::new(6, 0);
+        assert_eq!(evening.minutes_until(morning), 10 * 60);
+        assert_eq!(morning.minutes_until(evening), 14 * 60);
+        assert_eq!(morning.minutes_until(morning), 0);
+        assert!(GameClock::new(23, 0).is_between(evening, morning));
+        assert!(GameClock::new(5, 59).is_between(evening, morning));
+        assert!(!morning.is_between(evening, morning));
+        assert!(GameClock::NOON.is_between(morning, evening));
+    }
+}
@@ -0,0 +1,60 @@
+/// 爆炸类型, 对应 `create_explosion` 的 type 参数
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ExplosionType {
+    Grenade, // 第一个变体，值=0
+    Molotov,
+    Rocket,
+    Car,
+    CarQuick,
+    Boat,
+    Heli,
+    Heli2,
+    Mine,
+    Barrel,
+    TankGrenade,
+    HeliBomb,
+}
+
+impl ExplosionType {
+    /// 所有爆炸类型
+    pub const ALL: [ExplosionType; 12] = [
+        Self::Grenade,
+        Self::Molotov,
+        Self::Rocket,
+        Self::Car,
+        Self::CarQuick,
+        Self::Boat,
+        Self::Heli,
+        Self::Heli2,
+        Self::Mine,
+        Self::Barrel,
+        Self::TankGrenade,
+        Self::HeliBomb,
+    ];
+}
+
+impl From<i32> for ExplosionType {
+    fn from(value: i32) -> Self {
+        match value {
+            0 => Self::Grenade,
+            1 => Self::Molotov,
+            2 => Self::Rocket,
+            3 => Self::Car,
+            4 => Self::CarQuick,
+            5 => Self::Boat,
+            6 => Self::Heli,
+            7 => Self::Heli2,
+            8 => Self::Mine,
+            9 => Self::Barrel,
+            10 => Self::TankGrenade,
+            11 => Self::HeliBomb,
+            _ => Self::Grenade, // 未知值转为第一个变体
+        }
+    }
+}
+
+impl From<ExplosionType> for i32 {
+    fn from(val: ExplosionType) -> Self {
+        val as i32
+    }
+}
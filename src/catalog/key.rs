@@ -0,0 +1,382 @@
+/// 按键, 对应 `register_key_bind` 的 key, 值是 Windows 的虚拟键码
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum KeyCode {
+    /// 没有按键, `register_key_bind` 的 key2 / key3 不用时传这个
+    #[default]
+    None = 0x00, // 第一个变体，值=0
+    LeftButton = 0x01,
+    RightButton = 0x02,
+    MiddleButton = 0x04,
+    Backspace = 0x08,
+    Tab = 0x09,
+    Enter = 0x0D,
+    Shift = 0x10,
+    Control = 0x11,
+    Alt = 0x12,
+    Pause = 0x13,
+    CapsLock = 0x14,
+    Escape = 0x1B,
+    Space = 0x20,
+    PageUp = 0x21,
+    PageDown = 0x22,
+    End = 0x23,
+    Home = 0x24,
+    Left = 0x25,
+    Up = 0x26,
+    Right = 0x27,
+    Down = 0x28,
+    Insert = 0x2D,
+    Delete = 0x2E,
+    Num0 = 0x30,
+    Num1 = 0x31,
+    Num2 = 0x32,
+    Num3 = 0x33,
+    Num4 = 0x34,
+    Num5 = 0x35,
+    Num6 = 0x36,
+    Num7 = 0x37,
+    Num8 = 0x38,
+    Num9 = 0x39,
+    A = 0x41,
+    B = 0x42,
+    C = 0x43,
+    D = 0x44,
+    E = 0x45,
+    F = 0x46,
+    G = 0x47,
+    H = 0x48,
+    I = 0x49,
+    J = 0x4A,
+    K = 0x4B,
+    L = 0x4C,
+    M = 0x4D,
+    N = 0x4E,
+    O = 0x4F,
+    P = 0x50,
+    Q = 0x51,
+    R = 0x52,
+    S = 0x53,
+    T = 0x54,
+    U = 0x55,
+    V = 0x56,
+    W = 0x57,
+    X = 0x58,
+    Y = 0x59,
+    Z = 0x5A,
+    Numpad0 = 0x60,
+    Numpad1 = 0x61,
+    Numpad2 = 0x62,
+    Numpad3 = 0x63,
+    Numpad4 = 0x64,
+    Numpad5 = 0x65,
+    Numpad6 = 0x66,
+    Numpad7 = 0x67,
+    Numpad8 = 0x68,
+    Numpad9 = 0x69,
+    Multiply = 0x6A,
+    Add = 0x6B,
+    Subtract = 0x6D,
+    Decimal = 0x6E,
+    Divide = 0x6F,
+    F1 = 0x70,
+    F2 = 0x71,
+    F3 = 0x72,
+    F4 = 0x73,
+    F5 = 0x74,
+    F6 = 0x75,
+    F7 = 0x76,
+    F8 = 0x77,
+    F9 = 0x78,
+    F10 = 0x79,
+    F11 = 0x7A,
+    F12 = 0x7B,
+    NumLock = 0x90,
+    ScrollLock = 0x91,
+    LeftShift = 0xA0,
+    RightShift = 0xA1,
+    LeftControl = 0xA2,
+    RightControl = 0xA3,
+    LeftAlt = 0xA4,
+    RightAlt = 0xA5,
+    /// `;:`
+    Semicolon = 0xBA,
+    /// `=+`
+    Plus = 0xBB,
+    Comma = 0xBC,
+    Minus = 0xBD,
+    Period = 0xBE,
+    /// `/?`
+    Slash = 0xBF,
+    /// `` `~ ``
+    Tilde = 0xC0,
+    LeftBracket = 0xDB,
+    Backslash = 0xDC,
+    RightBracket = 0xDD,
+    Quote = 0xDE,
+}
+
+impl KeyCode {
+    /// 所有按键
+    pub const ALL: [KeyCode; 106] = [
+        Self::None,
+        Self::LeftButton,
+        Self::RightButton,
+        Self::MiddleButton,
+        Self::Backspace,
+        Self::Tab,
+        Self::Enter,
+        Self::Shift,
+        Self::Control,
+        Self::Alt,
+        Self::Pause,
+        Self::CapsLock,
+        Self::Escape,
+        Self::Space,
+        Self::PageUp,
+        Self::PageDown,
+        Self::End,
+        Self::Home,
+        Self::Left,
+        Self::Up,
+        Self::Right,
+        Self::Down,
+        Self::Insert,
+        Self::Delete,
+        Self::Num0,
+        Self::Num1,
+        Self::Num2,
+        Self::Num3,
+        Self::Num4,
+        Self::Num5,
+        Self::Num6,
+        Self::Num7,
+        Self::Num8,
+        Self::Num9,
+        Self::A,
+        Self::B,
+        Self::C,
+        Self::D,
+        Self::E,
+        Self::F,
+        Self::G,
+        Self::H,
+        Self::I,
+        Self::J,
+        Self::K,
+        Self::L,
+        Self::M,
+        Self::N,
+        Self::O,
+        Self::P,
+        Self::Q,
+        Self::R,
+        Self::S,
+        Self::T,
+        Self::U,
+        Self::V,
+        Self::W,
+        Self::X,
+        Self::Y,
+        Self::Z,
+        Self::Numpad0,
+        Self::Numpad1,
+        Self::Numpad2,
+        Self::Numpad3,
+        Self::Numpad4,
+        Self::Numpad5,
+        Self::Numpad6,
+        Self::Numpad7,
+        Self::Numpad8,
+        Self::Numpad9,
+        Self::Multiply,
+        Self::Add,
+        Self::Subtract,
+        Self::Decimal,
+        Self::Divide,
+        Self::F1,
+        Self::F2,
+        Self::F3,
+        Self::F4,
+        Self::F5,
+        Self::F6,
+        Self::F7,
+        Self::F8,
+        Self::F9,
+        Self::F10,
+        Self::F11,
+        Self::F12,
+        Self::NumLock,
+        Self::ScrollLock,
+        Self::LeftShift,
+        Self::RightShift,
+        Self::LeftControl,
+        Self::RightControl,
+        Self::LeftAlt,
+        Self::RightAlt,
+        Self::Semicolon,
+        Self::Plus,
+        Self::Comma,
+        Self::Minus,
+        Self::Period,
+        Self::Slash,
+        Self::Tilde,
+        Self::LeftBracket,
+        Self::Backslash,
+        Self::RightBracket,
+        Self::Quote,
+    ];
+}
+
+impl From<i32> for KeyCode {
+    fn from(value: i32) -> Self {
+        match value {
+            0x00 => Self::None,
+            0x01 => Self::LeftButton,
+            0x02 => Self::RightButton,
+            0x04 => Self::MiddleButton,
+            0x08 => Self::Backspace,
+            0x09 => Self::Tab,
+            0x0D => Self::Enter,
+            0x10 => Self::Shift,
+            0x11 => Self::Control,
+            0x12 => Self::Alt,
+            0x13 => Self::Pause,
+            0x14 => Self::CapsLock,
+            0x1B => Self::Escape,
+            0x20 => Self::Space,
+            0x21 => Self::PageUp,
+            0x22 => Self::PageDown,
+            0x23 => Self::End,
+            0x24 => Self::Home,
+            0x25 => Self::Left,
+            0x26 => Self::Up,
+            0x27 => Self::Right,
+            0x28 => Self::Down,
+            0x2D => Self::Insert,
+            0x2E => Self::Delete,
+            0x30 => Self::Num0,
+            0x31 => Self::Num1,
+            0x32 => Self::Num2,
+            0x33 => Self::Num3,
+            0x34 => Self::Num4,
+            0x35 => Self::Num5,
+            0x36 => Self::Num6,
+            0x37 => Self::Num7,
+            0x38 => Self::Num8,
+            0x39 => Self::Num9,
+            0x41 => Self::A,
+            0x42 => Self::B,
+            0x43 => Self::C,
+            0x44 => Self::D,
+            0x45 => Self::E,
+            0x46 => Self::F,
+            0x47 => Self::G,
+            0x48 => Self::H,
+            0x49 => Self::I,
+            0x4A => Self::J,
+            0x4B => Self::K,
+            0x4C => Self::L,
+            0x4D => Self::M,
+            0x4E => Self::N,
+            0x4F => Self::O,
+            0x50 => Self::P,
+            0x51 => Self::Q,
+            0x52 => Self::R,
+            0x53 => Self::S,
+            0x54 => Self::T,
+            0x55 => Self::U,
+            0x56 => Self::V,
+            0x57 => Self::W,
+            0x58 => Self::X,
+            0x59 => Self::Y,
+            0x5A => Self::Z,
+            0x60 => Self::Numpad0,
+            0x61 => Self::Numpad1,
+            0x62 => Self::Numpad2,
+            0x63 => Self::Numpad3,
+            0x64 => Self::Numpad4,
+            0x65 => Self::Numpad5,
+            0x66 => Self::Numpad6,
+            0x67 => Self::Numpad7,
+            0x68 => Self::Numpad8,
+            0x69 => Self::Numpad9,
+            0x6A => Self::Multiply,
+            0x6B => Self::Add,
+            0x6D => Self::Subtract,
+            0x6E => Self::Decimal,
+            0x6F => Self::Divide,
+            0x70 => Self::F1,
+            0x71 => Self::F2,
+            0x72 => Self::F3,
+            0x73 => Self::F4,
+            0x74 => Self::F5,
+            0x75 => Self::F6,
+            0x76 => Self::F7,
+            0x77 => Self::F8,
+            0x78 => Self::F9,
+            0x79 => Self::F10,
+            0x7A => Self::F11,
+            0x7B => Self::F12,
+            0x90 => Self::NumLock,
+            0x91 => Self::ScrollLock,
+            0xA0 => Self::LeftShift,
+            0xA1 => Self::RightShift,
+            0xA2 => Self::LeftControl,
+            0xA3 => Self::RightControl,
+            0xA4 => Self::LeftAlt,
+            0xA5 => Self::RightAlt,
+            0xBA => Self::Semicolon,
+            0xBB => Self::Plus,
+            0xBC => Self::Comma,
+            0xBD => Self::Minus,
+            0xBE => Self::Period,
+            0xBF => Self::Slash,
+            0xC0 => Self::Tilde,
+            0xDB => Self::LeftBracket,
+            0xDC => Self::Backslash,
+            0xDD => Self::RightBracket,
+            0xDE => Self::Quote,
+            _ => Self::None, // 未知值转为第一个变体
+        }
+    }
+}
+
+impl From<KeyCode> for i32 {
+    fn from(val: KeyCode) -> Self {
+        val as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_virtual_key_code_decodes_consistently() {
+        let mut decoded = Vec::new();
+        for code in 0..=0xFFFF {
+            let key = KeyCode::from(code);
+            if key == KeyCode::None {
+                continue;
+            }
+            // 认识的值必须原样转回去
+            assert_eq!(i32::from(key), code);
+            assert!(KeyCode::ALL.contains(&key), "{key:?} missing from ALL");
+            decoded.push(key);
+        }
+        // ALL 里除了 None 的每一个都能从自己的值解出来, 没有漏写 match 分支
+        assert_eq!(decoded.len() + 1, KeyCode::ALL.len());
+        for key in KeyCode::ALL {
+            assert_eq!(KeyCode::from(i32::from(key)), key);
+        }
+    }
+
+    #[test]
+    fn unknown_codes_fall_back_to_none() {
+        // 0x07 是保留的虚拟键码
+        for code in [-1, 0x07, 0x100, i32::MIN, i32::MAX] {
+            assert_eq!(KeyCode::from(code), KeyCode::None, "code {code:#x}");
+        }
+        assert_eq!(i32::from(KeyCode::None), 0);
+    }
+}
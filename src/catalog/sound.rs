@@ -0,0 +1,36 @@
+/// 音效 id
+///
+/// 只是包一层防止和别的 i32 搞混. 带名字的音效表没有可以核对的来源,
+/// 还没有做, 现在需要名字的话在自己的代码里定义常量:
+///
+/// ```
+/// use vcmp_bindings::catalog::Sound;
+///
+/// const MY_SOUND: Sound = Sound::new(1);
+/// assert_eq!(MY_SOUND.id(), 1);
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Sound(pub i32);
+
+impl Sound {
+    pub const fn new(id: i32) -> Self {
+        Self(id)
+    }
+
+    pub const fn id(&self) -> i32 {
+        self.0
+    }
+}
+
+impl From<i32> for Sound {
+    fn from(value: i32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Sound> for i32 {
+    fn from(val: Sound) -> Self {
+        val.0
+    }
+}
+
@@ -0,0 +1,47 @@
+/// 天气, 对应 `set_weather` / `get_weather`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum Weather {
+    #[default]
+    Sunny, // 第一个变体，值=0
+    Cloudy,
+    Rainy,
+    Foggy,
+    ExtraSunny,
+    Hurricane,
+    /// 室内用的天气
+    ExtraColours,
+}
+
+impl Weather {
+    /// 所有天气
+    pub const ALL: [Weather; 7] = [
+        Self::Sunny,
+        Self::Cloudy,
+        Self::Rainy,
+        Self::Foggy,
+        Self::ExtraSunny,
+        Self::Hurricane,
+        Self::ExtraColours,
+    ];
+}
+
+impl From<i32> for Weather {
+    fn from(value: i32) -> Self {
+        match value {
+            0 => Self::Sunny,
+            1 => Self::Cloudy,
+            2 => Self::Rainy,
+            3 => Self::Foggy,
+            4 => Self::ExtraSunny,
+            5 => Self::Hurricane,
+            6 => Self::ExtraColours,
+            _ => Self::Sunny, // 未知值转为第一个变体
+        }
+    }
+}
+
+impl From<Weather> for i32 {
+    fn from(val: Weather) -> Self {
+        val as i32
+    }
+}
@@ -0,0 +1,46 @@
+use std::time::Duration;
+
+use crate::events::server::ServerFrameEvent;
+
+/// 用 ServerFrame 的 elapsed_time 累加出来的时钟
+///
+/// 服务器不会告诉我们现在是什么时候, 各种定时的东西都靠这个
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrameClock {
+    now: Duration,
+}
+
+impl FrameClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 往前走一帧, 返回这一帧的时长
+    pub fn advance(&mut self, event: &ServerFrameEvent) -> Duration {
+        let elapsed = Duration::from_secs_f32(event.elapsed_time.max(0.0));
+        self.now += elapsed;
+        elapsed
+    }
+
+    /// 从开始累计到现在的时间
+    pub fn now(&self) -> Duration {
+        self.now
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_clock_accumulates() {
+        let mut clock = FrameClock::new();
+        assert_eq!(
+            clock.advance(&ServerFrameEvent::from(0.5)),
+            Duration::from_millis(500)
+        );
+        clock.advance(&ServerFrameEvent::from(-1.0));
+        clock.advance(&ServerFrameEvent::from(0.25));
+        assert_eq!(clock.now(), Duration::from_millis(750));
+    }
+}
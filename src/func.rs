@@ -21,15 +21,18 @@ pub mod weapon;
 // use 所有的 trait
 pub use admin::AdministrationMethods;
 pub use checkpoint::CheckPointMethods;
-pub use environment::{EnvironmentMethods, QueryEnvironmentOption, SetEnvironmentOption};
+pub use environment::{
+    EnvironmentMethods, QueryEnvironmentOption, QueryEnvironmentWorld, SetEnvironmentOption,
+    SetEnvironmentWorld,
+};
 pub use keybind::KeybindMethods;
 pub use marker::MarkerMethods;
 pub use misc::MiscMethods;
-pub use network::QueryNetworkStatistics;
+pub use network::{NetworkStats, QueryNetworkStatistics};
 pub use object::ObjectMethods;
 pub use pickup::PickupMethods;
 pub use player::PlayerMethods;
-pub use plugin::PluginMethods;
+pub use plugin::{ExportTable, PluginMethods};
 pub use server::ServerMethods;
 pub use vehicle::{
     QueryVehicle, QueryVehicleOptions, SetVehicle, SetVehicleOptions, VehicleHandlingMethods,
@@ -1,21 +1,52 @@
 use crate::func::VcmpFunctions;
+use crate::types::IpRange;
+use crate::{VcmpError, VcmpResult};
 
 pub trait AdministrationMethods {
-    fn ban_ip(&self, ip: &str);
+    /// 加进服务器自带的封禁列表
+    ///
+    /// 只接受单个地址和 IPv4 通配符 (`1.2.3.*`), 其他写法返回 `ArgumentOutOfBounds`.
+    /// `*.*.*.*` 会封掉所有人, 也返回 `ArgumentOutOfBounds`.
+    /// CIDR 之类的段请用 [`crate::moderation::IpBanTable`], 真要封掉所有人在那里写 `0.0.0.0/0`
+    fn ban_ip(&self, ip: &str) -> VcmpResult<()>;
+    /// 不合法的地址返回 false
     fn unban_ip(&self, ip: &str) -> bool;
+    /// 不合法的地址返回 false
     fn is_ip_banned(&self, ip: &str) -> bool;
 }
 
+/// 转成服务器认识的写法
+fn native_ip(ip: &str) -> Option<String> {
+    ip.parse::<IpRange>().ok()?.native_form()
+}
+
+/// 和 `native_ip` 一样, 但是不接受包含所有地址的段
+fn native_ban_ip(ip: &str) -> Option<String> {
+    let range = ip.parse::<IpRange>().ok()?;
+    if range.is_everything() {
+        return None;
+    }
+    range.native_form()
+}
+
 impl AdministrationMethods for VcmpFunctions {
-    fn ban_ip(&self, ip: &str) {
+    fn ban_ip(&self, ip: &str) -> VcmpResult<()> {
+        let ip = native_ban_ip(ip).ok_or(VcmpError::ArgumentOutOfBounds)?;
         let addr = format!("{ip}\0");
         (self.inner.BanIP)(addr.as_ptr() as *mut i8);
+        Ok(())
     }
     fn unban_ip(&self, ip: &str) -> bool {
+        let Some(ip) = native_ip(ip) else {
+            return false;
+        };
         let addr = format!("{ip}\0");
         (self.inner.UnbanIP)(addr.as_ptr() as *mut i8) != 0
     }
     fn is_ip_banned(&self, ip: &str) -> bool {
+        let Some(ip) = native_ip(ip) else {
+            return false;
+        };
         let addr = format!("{ip}\0");
         (self.inner.IsIPBanned)(addr.as_ptr() as *mut i8) != 0
     }
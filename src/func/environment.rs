@@ -1,3 +1,4 @@
+use crate::catalog::{GameClock, Weather};
 use crate::utils::{Color, Vectorf32, WastedSettings, WorldBounds};
 use crate::{VcmpFunctions, options::VcmpServerOption};
 
@@ -337,8 +338,9 @@ pub trait SetEnvironmentWorld {
     fn set_time(&self, time: i32);
     fn set_hour(&self, hour: i32);
     fn set_minute(&self, minute: i32);
+    fn set_game_clock(&self, clock: GameClock);
     fn set_water_level(&self, level: f32);
-    fn set_weather(&self, weather: i32);
+    fn set_weather(&self, weather: Weather);
     fn set_gravity(&self, gravity: f32);
     fn set_gamespeed(&self, gamespeed: f32);
     fn set_maximum_flight_altitude(&self, height: f32);
@@ -363,11 +365,15 @@ impl SetEnvironmentWorld for VcmpFunctions {
     fn set_minute(&self, minute: i32) {
         (self.inner.SetMinute)(minute);
     }
+    fn set_game_clock(&self, clock: GameClock) {
+        self.set_hour(clock.hour() as i32);
+        self.set_minute(clock.minute() as i32);
+    }
     fn set_water_level(&self, level: f32) {
         (self.inner.SetWaterLevel)(level);
     }
-    fn set_weather(&self, weather: i32) {
-        (self.inner.SetWeather)(weather);
+    fn set_weather(&self, weather: Weather) {
+        (self.inner.SetWeather)(weather.into());
     }
     fn set_gravity(&self, gravity: f32) {
         (self.inner.SetGravity)(gravity);
@@ -394,8 +400,9 @@ pub trait QueryEnvironmentWorld {
     fn get_time(&self) -> i32;
     fn get_hour(&self) -> i32;
     fn get_minute(&self) -> i32;
+    fn get_game_clock(&self) -> GameClock;
     fn get_water_level(&self) -> f32;
-    fn get_weather(&self) -> i32;
+    fn get_weather(&self) -> Weather;
     fn get_gravity(&self) -> f32;
     fn get_gamespeed(&self) -> f32;
     fn get_maximum_flight_altitude(&self) -> f32;
@@ -415,11 +422,14 @@ impl QueryEnvironmentWorld for VcmpFunctions {
     fn get_minute(&self) -> i32 {
         (self.inner.GetMinute)()
     }
+    fn get_game_clock(&self) -> GameClock {
+        GameClock::from(self.get_time())
+    }
     fn get_water_level(&self) -> f32 {
         (self.inner.GetWaterLevel)()
     }
-    fn get_weather(&self) -> i32 {
-        (self.inner.GetWeather)()
+    fn get_weather(&self) -> Weather {
+        Weather::from((self.inner.GetWeather)())
     }
     fn get_gravity(&self) -> f32 {
         (self.inner.GetGravity)()
@@ -1,6 +1,7 @@
 use crate::func::VcmpFunctions;
 use crate::options::VcmpEntityPool;
 use crate::utils::{Color, Marker, Vectorf32};
+use crate::{VcmpError, VcmpResult};
 
 pub trait MarkerMethods {
     fn create_marker(
@@ -12,7 +13,9 @@ pub trait MarkerMethods {
         sprite: i32,
         index: Option<i32>,
     ) -> i32;
+    #[deprecated(note = "拼错了, 请用 `destroy_marker`")]
     fn destory_marker(&self, marker: i32);
+    fn destroy_marker(&self, marker: i32) -> VcmpResult<()>;
     fn is_marker_alive(&self, marker: i32) -> bool;
     fn get_marker_info(&self, marker: i32) -> Marker;
 }
@@ -45,6 +48,14 @@ impl MarkerMethods for VcmpFunctions {
     fn destory_marker(&self, marker: i32) {
         (self.inner.DestroyCoordBlip)(marker);
     }
+    fn destroy_marker(&self, marker: i32) -> VcmpResult<()> {
+        let code = (self.inner.DestroyCoordBlip)(marker);
+        if code != 0 {
+            Err(VcmpError::from(code))
+        } else {
+            Ok(())
+        }
+    }
 
     fn get_marker_info(&self, marker: i32) -> Marker {
         let (mut world, mut x, mut y, mut z, mut scale, mut color, mut sprite) =
@@ -1,17 +1,44 @@
-use crate::func::VcmpFunctions;
-
+use crate::catalog::{ExplosionType, Sound};
+use crate::func::{PlayerMethods, VcmpFunctions};
 use crate::utils::Vectorf32;
+use crate::{PlayerId, VcmpError, VcmpResult};
+
+/// HideMapObject / ShowMapObject 用的坐标 (单位是 0.1)
+pub fn map_object_tenths(pos: Vectorf32) -> (i16, i16, i16) {
+    let x = ((pos.x * 10.0).floor() + 0.5) as i16;
+    let y = ((pos.y * 10.0).floor() + 0.5) as i16;
+    let z = ((pos.z * 10.0).floor() + 0.5) as i16;
+    (x, y, z)
+}
 
 pub trait MiscMethods {
+    /// 制造爆炸
+    ///
+    /// `responsible_player` 为 None 或者玩家不在线时不算在任何人头上
     fn create_explosion(
         &self,
         world: i32,
-        explosion_type: i32,
+        explosion_type: ExplosionType,
         pos: Vectorf32,
-        responsible_player: i32,
+        responsible_player: Option<PlayerId>,
         on_ground: bool,
-    );
-    fn play_sound(&self, world: i32, sound: i32, x: f32, y: f32, z: f32);
+    ) -> VcmpResult<()>;
+    /// 在世界里播放音效, 所有能看到这个世界的玩家都能听到
+    ///
+    /// position 为 None 时不是 3d 音效
+    fn play_sound(&self, world: i32, sound: Sound, position: Option<Vectorf32>) -> VcmpResult<()>;
+    /// 只给主世界是 `world` 的玩家播放音效
+    fn play_sound_in_world(&self, world: i32, sound: Sound, position: Option<Vectorf32>);
+    /// 给 `world` 里距离 `position` 不超过 `radius` 的玩家播放音效
+    ///
+    /// 返回听到的玩家
+    fn play_sound_in_radius(
+        &self,
+        world: i32,
+        sound: Sound,
+        position: Vectorf32,
+        radius: f32,
+    ) -> Vec<PlayerId>;
     fn hide_map_object(&self, object_id: i32, pos: Vectorf32);
     fn show_map_object(&self, object_id: i32, pos: Vectorf32);
     fn show_all_map_objects(&self);
@@ -23,38 +50,79 @@ impl MiscMethods for VcmpFunctions {
     fn create_explosion(
         &self,
         world: i32,
-        explosion_type: i32,
+        explosion_type: ExplosionType,
         pos: Vectorf32,
-        responsible_player: i32,
+        responsible_player: Option<PlayerId>,
         on_ground: bool,
-    ) {
-        (self.inner.CreateExplosion)(
+    ) -> VcmpResult<()> {
+        let responsible_player = responsible_player
+            .filter(|&player| self.is_player_connected(player))
+            .unwrap_or(-1);
+        let code = (self.inner.CreateExplosion)(
             world,
-            explosion_type,
+            explosion_type.into(),
             pos.x,
             pos.y,
             pos.z,
             responsible_player,
             on_ground as u8,
         );
+        if code != 0 {
+            Err(VcmpError::from(code))
+        } else {
+            Ok(())
+        }
     }
-    fn play_sound(&self, world: i32, sound: i32, x: f32, y: f32, z: f32) {
-        (self.inner.PlaySound)(world, sound, x, y, z);
+    fn play_sound(&self, world: i32, sound: Sound, position: Option<Vectorf32>) -> VcmpResult<()> {
+        let pos = position.unwrap_or(Vectorf32 {
+            x: f32::NAN,
+            y: f32::NAN,
+            z: f32::NAN,
+        });
+        let code = (self.inner.PlaySound)(world, sound.into(), pos.x, pos.y, pos.z);
+        if code != 0 {
+            Err(VcmpError::from(code))
+        } else {
+            Ok(())
+        }
+    }
+    fn play_sound_in_world(&self, world: i32, sound: Sound, position: Option<Vectorf32>) {
+        for player in self.get_connected_players() {
+            if self.get_player_world(player) == world {
+                let _ = self.play_sound_for_player(player, sound, position);
+            }
+        }
+    }
+    fn play_sound_in_radius(
+        &self,
+        world: i32,
+        sound: Sound,
+        position: Vectorf32,
+        radius: f32,
+    ) -> Vec<PlayerId> {
+        self.get_connected_players()
+            .into_iter()
+            .filter(|&player| self.is_player_world_compatible(player, world))
+            .filter(|&player| {
+                self.get_player_position(player).is_ok_and(|pos| {
+                    let (dx, dy, dz) = (pos.x - position.x, pos.y - position.y, pos.z - position.z);
+                    (dx * dx + dy * dy + dz * dz).sqrt() <= radius
+                })
+            })
+            .filter(|&player| {
+                self.play_sound_for_player(player, sound, Some(position))
+                    .is_ok()
+            })
+            .collect()
     }
     fn hide_map_object(&self, object_id: i32, pos: Vectorf32) {
-        let (x, y, z) = (pos.x, pos.y, pos.z);
-        let x = ((x * 10.0).floor() + 0.5) as i16;
-        let y = ((y * 10.0).floor() + 0.5) as i16;
-        let z = ((z * 10.0).floor() + 0.5) as i16;
+        let (x, y, z) = map_object_tenths(pos);
 
         (self.inner.HideMapObject)(object_id, x, y, z);
     }
 
     fn show_map_object(&self, object_id: i32, pos: Vectorf32) {
-        let (x, y, z) = (pos.x, pos.y, pos.z);
-        let x = ((x * 10.0).floor() + 0.5) as i16;
-        let y = ((y * 10.0).floor() + 0.5) as i16;
-        let z = ((z * 10.0).floor() + 0.5) as i16;
+        let (x, y, z) = map_object_tenths(pos);
 
         (self.inner.ShowMapObject)(object_id, x, y, z);
     }
@@ -1,6 +1,46 @@
 use crate::{PlayerId, VcmpFunctions, options::VcmpNetworkStatisticsQueryOption};
 
+/// 某个玩家某一时刻的全部网络统计
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct NetworkStats {
+    pub data_sent_per_second: f64,
+    pub data_resent_per_second: f64,
+    pub data_received_per_second: f64,
+    pub data_discarded_per_second: f64,
+    pub all_bytes_sent_per_second: f64,
+    pub all_bytes_received_per_second: f64,
+
+    pub data_sent_total: f64,
+    pub data_resent_total: f64,
+    pub data_received_total: f64,
+    pub data_discarded_total: f64,
+    pub all_bytes_sent_total: f64,
+    pub all_bytes_received_total: f64,
+
+    pub messages_waiting: f64,
+    pub messages_resending: f64,
+    pub bytes_resending: f64,
+
+    /// 0 ~ 1 的比例
+    pub packet_loss_per_second: f64,
+    pub packet_loss_total: f64,
+}
+
+impl NetworkStats {
+    /// 每秒重发的数据占发送数据的比例, 没有发送时为 0
+    pub fn resend_ratio(&self) -> f64 {
+        if self.data_sent_per_second > 0.0 {
+            self.data_resent_per_second / self.data_sent_per_second
+        } else {
+            0.0
+        }
+    }
+}
+
 pub trait QueryNetworkStatistics {
+    /// 一次取出所有统计
+    fn network_stats(&self, player_id: PlayerId) -> NetworkStats;
+
     fn data_sent_per_second(&self, player_id: PlayerId) -> f64;
     fn data_resent_per_second(&self, player_id: PlayerId) -> f64;
     fn data_received_per_second(&self, player_id: PlayerId) -> f64;
@@ -24,6 +64,28 @@ pub trait QueryNetworkStatistics {
 }
 
 impl QueryNetworkStatistics for VcmpFunctions {
+    fn network_stats(&self, player_id: PlayerId) -> NetworkStats {
+        NetworkStats {
+            data_sent_per_second: self.data_sent_per_second(player_id),
+            data_resent_per_second: self.data_resent_per_second(player_id),
+            data_received_per_second: self.data_received_per_second(player_id),
+            data_discarded_per_second: self.data_discarded_per_second(player_id),
+            all_bytes_sent_per_second: self.all_bytes_sent_per_second(player_id),
+            all_bytes_received_per_second: self.all_bytes_received_per_second(player_id),
+            data_sent_total: self.data_sent_total(player_id),
+            data_resent_total: self.data_resent_total(player_id),
+            data_received_total: self.data_received_total(player_id),
+            data_discarded_total: self.data_discarded_total(player_id),
+            all_bytes_sent_total: self.all_bytes_sent_total(player_id),
+            all_bytes_received_total: self.all_bytes_received_total(player_id),
+            messages_waiting: self.messages_waiting(player_id),
+            messages_resending: self.messages_resending(player_id),
+            bytes_resending: self.bytes_resending(player_id),
+            packet_loss_per_second: self.packet_loss_per_second(player_id),
+            packet_loss_total: self.packet_loss_total(player_id),
+        }
+    }
+
     fn data_sent_per_second(&self, player_id: PlayerId) -> f64 {
         self.get_network_statistics(
             player_id,
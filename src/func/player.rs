@@ -1,5 +1,7 @@
 use std::ffi::c_void;
 
+use crate::catalog::{AnnounceStyle, Sound};
+use crate::encodes::encode_to_gbk;
 use crate::options::VcmpPlayerOption;
 use crate::states::VcmpPlayerState;
 use crate::utils::{Color, Vectorf32};
@@ -10,6 +12,8 @@ pub trait PlayerMethods {
     fn send_client_script_data(&self, player_id: PlayerId, data: &[u8]) -> VcmpResult<()>;
 
     /// 发送消息
+    ///
+    /// 消息按 GBK 原样发送, 里面的 `%` 不会被当成格式符, 可以直接转发玩家打的字
     fn send_client_message(
         &self,
         player_id: PlayerId,
@@ -21,11 +25,25 @@ pub trait PlayerMethods {
     fn send_announce(
         &self,
         player_id: PlayerId,
-        announce_type: i32,
+        style: AnnounceStyle,
         message: &str,
     ) -> VcmpResult<()>;
 
-    fn play_sound_for_player(&self, player_id: PlayerId, sound: i32, position: Option<Vectorf32>);
+    /// 给所有在线玩家发送公告
+    fn send_announce_to_all(&self, style: AnnounceStyle, message: &str);
+
+    /// 给指定世界的玩家发送公告
+    fn send_announce_to_world(&self, world: i32, style: AnnounceStyle, message: &str);
+
+    /// 只给这个玩家播放音效
+    ///
+    /// 用的是玩家的 unique world, 只有这个玩家自己能看到这个世界
+    fn play_sound_for_player(
+        &self,
+        player_id: PlayerId,
+        sound: Sound,
+        position: Option<Vectorf32>,
+    ) -> VcmpResult<()>;
 
     /*
         Admins?
@@ -40,12 +58,17 @@ pub trait PlayerMethods {
     fn ban_player(&self, player_id: PlayerId);
 
     fn is_player_connected(&self, player: i32) -> bool;
+    /// 所有在线玩家的 id
+    fn get_connected_players(&self) -> Vec<PlayerId>;
     fn is_player_streamed_for_target(&self, player: i32, target: i32) -> bool;
 
     fn get_player_key(&self, player: i32) -> u32;
 
     fn get_player_name(&self, player: i32) -> String;
 
+    /// 按名字 (完全一致) 找到玩家
+    fn get_player_id_from_name(&self, name: &str) -> Option<PlayerId>;
+
     fn set_player_name(&self, player: i32, name: &str);
 
     fn get_player_state(&self, player: i32) -> VcmpPlayerState;
@@ -194,10 +217,11 @@ impl PlayerMethods for VcmpFunctions {
         let color = color.as_rgba();
         // set rgba, a to 255
         let color = (color & 0xFFFFFF00) | 0x000000FF;
-        // append \0
-        let msg = format!("{message}\0");
+        let mut msg = encode_to_gbk(message).to_vec();
+        msg.push(0); // 到 C 层面要加一个 \0
         let msg_ptr = msg.as_ptr() as *const i8;
-        let code = (self.inner.SendClientMessage)(player_id, color, msg_ptr);
+        // format 是可变参数, 玩家打的字里可能有 %s %n, 不能直接当 format 用
+        let code = (self.inner.SendClientMessage)(player_id, color, c"%s".as_ptr(), msg_ptr);
         if code != 0 {
             Err(VcmpError::from(code))
         } else {
@@ -209,13 +233,14 @@ impl PlayerMethods for VcmpFunctions {
     fn send_announce(
         &self,
         player_id: PlayerId,
-        announce_type: i32,
+        style: AnnounceStyle,
         message: &str,
     ) -> VcmpResult<()> {
-        let zero_msg = format!("{message}\0");
-        let msg = zero_msg.as_bytes();
+        let mut msg = encode_to_gbk(message).to_vec();
+        msg.push(0); // 到 C 层面要加一个 \0
         let msg_ptr = msg.as_ptr() as *const i8;
-        let code = (self.inner.SendGameMessage)(player_id, announce_type, msg_ptr);
+        // format 是可变参数, 消息里的 % 不能直接当 format 用
+        let code = (self.inner.SendGameMessage)(player_id, style.into(), c"%s".as_ptr(), msg_ptr);
         if code != 0 {
             Err(VcmpError::from(code))
         } else {
@@ -223,11 +248,34 @@ impl PlayerMethods for VcmpFunctions {
         }
     }
 
+    fn send_announce_to_all(&self, style: AnnounceStyle, message: &str) {
+        for player in self.get_connected_players() {
+            let _ = self.send_announce(player, style, message);
+        }
+    }
+
+    fn send_announce_to_world(&self, world: i32, style: AnnounceStyle, message: &str) {
+        for player in self.get_connected_players() {
+            if self.get_player_world(player) == world {
+                let _ = self.send_announce(player, style, message);
+            }
+        }
+    }
+
     /*
 
     */
 
-    fn play_sound_for_player(&self, player_id: PlayerId, sound: i32, position: Option<Vectorf32>) {
+    fn play_sound_for_player(
+        &self,
+        player_id: PlayerId,
+        sound: Sound,
+        position: Option<Vectorf32>,
+    ) -> VcmpResult<()> {
+        // 玩家不在线的时候拿不到有效的 unique world, 不能让声音落到别的世界里
+        if !self.is_player_connected(player_id) {
+            return Err(VcmpError::NoSuchEntity);
+        }
         let world = (self.inner.GetPlayerUniqueWorld)(player_id);
 
         let pos = position.unwrap_or(Vectorf32 {
@@ -236,7 +284,12 @@ impl PlayerMethods for VcmpFunctions {
             z: f32::NAN,
         });
         let (x, y, z) = (pos.x, pos.y, pos.z);
-        (self.inner.PlaySound)(world, sound, x, y, z);
+        let code = (self.inner.PlaySound)(world, sound.into(), x, y, z);
+        if code != 0 {
+            Err(VcmpError::from(code))
+        } else {
+            Ok(())
+        }
     }
 
     /*
@@ -279,6 +332,11 @@ impl PlayerMethods for VcmpFunctions {
     fn is_player_connected(&self, player: i32) -> bool {
         (self.inner.IsPlayerConnected)(player) != 0
     }
+    fn get_connected_players(&self) -> Vec<PlayerId> {
+        (0..(self.inner.GetMaxPlayers)() as i32)
+            .filter(|&player| self.is_player_connected(player))
+            .collect()
+    }
     fn is_player_streamed_for_target(&self, player: i32, target: i32) -> bool {
         (self.inner.IsPlayerStreamedForPlayer)(player, target) != 0
     }
@@ -294,6 +352,14 @@ impl PlayerMethods for VcmpFunctions {
         decode_gbk(&buf).trim_end_matches('\0').to_string()
     }
 
+    fn get_player_id_from_name(&self, name: &str) -> Option<PlayerId> {
+        let mut name = encode_to_gbk(name).to_vec();
+        name.push(0); // 到 C 层面要加一个 \0
+        let name_ptr = name.as_ptr() as *const i8;
+        let res = (self.inner.GetPlayerIdFromName)(name_ptr);
+        if res == -1 { None } else { Some(res) }
+    }
+
     fn set_player_name(&self, player: i32, name: &str) {
         let name = format!("{name}\0"); // append \0
         let name_ptr = name.as_ptr() as *const i8;
@@ -6,6 +6,25 @@ pub struct PluginExports {
     pub size: usize,
 }
 
+/// 可以通过 `ExportFunctions` 导出给别的插件的函数表
+///
+/// # Safety
+///
+/// 实现这个 trait 的类型必须是 `#[repr(C)]` 的, 并且里面只能放 `extern "C" fn`
+/// (或者同样满足这个要求的东西), 两边插件对它的布局理解要完全一致
+///
+/// ```no_run
+/// use vcmp_bindings::func::plugin::ExportTable;
+///
+/// #[repr(C)]
+/// pub struct MyExports {
+///     pub get_player_level: extern "C" fn(player_id: i32) -> i32,
+/// }
+///
+/// unsafe impl ExportTable for MyExports {}
+/// ```
+pub unsafe trait ExportTable: Sized + Sync + 'static {}
+
 pub trait PluginMethods {
     /// 获取插件(加载?)数量
     fn get_plugin_count(&self) -> u32;
@@ -20,6 +39,23 @@ pub trait PluginMethods {
 
     fn get_plugin_exports(&self, plugin_id: i32) -> PluginExports;
 
+    /// 把函数表导出到 `plugin_id` (自己的插件 id) 下面
+    ///
+    /// 和 C++ 插件一样传的是 "指向表指针的指针", 服务器只保存这个指针,
+    /// 所以表必须是 'static 的, 每次调用还会泄漏一个存表指针的槽位
+    fn export_functions<T: ExportTable>(&self, plugin_id: i32, table: &'static T)
+    -> VcmpResult<()>;
+
+    /// 拿到别的插件导出的函数表
+    ///
+    /// 对方没有导出时返回 `NoSuchEntity`, 导出的大小比 `T` 小时返回 `BufferTooSmall`
+    /// (比 `T` 大是允许的, 方便对方在末尾追加函数)
+    fn get_plugin_export_table<T: ExportTable>(&self, plugin_id: i32) -> VcmpResult<&'static T>;
+
+    /// 按插件名字找到导出的函数表
+    fn find_plugin_export_table<T: ExportTable>(&self, plugin_name: &str)
+    -> VcmpResult<&'static T>;
+
     fn get_plugins(&self) -> Vec<VcmpPluginInfo>;
 }
 
@@ -47,7 +83,8 @@ impl PluginMethods for VcmpFunctions {
     fn send_plugin_command(&self, command_identifier: u32, command: &str) -> VcmpResult<()> {
         let cmd = format!("{command}\0");
         let cmd_ptr = cmd.as_ptr() as *const i8;
-        let code = (self.inner.SendPluginCommand)(command_identifier, cmd_ptr);
+        // format 是可变参数, 命令里的 % 不能直接当 format 用
+        let code = (self.inner.SendPluginCommand)(command_identifier, c"%s".as_ptr(), cmd_ptr);
         if code != 0 {
             Err(VcmpError::from(code))
         } else {
@@ -64,6 +101,55 @@ impl PluginMethods for VcmpFunctions {
         }
     }
 
+    fn export_functions<T: ExportTable>(
+        &self,
+        plugin_id: i32,
+        table: &'static T,
+    ) -> VcmpResult<()> {
+        // 别的插件按 `*(T**)exports` 读, 所以要给它一个存着表指针的槽位
+        let slot: &'static mut *const std::os::raw::c_void =
+            Box::leak(Box::new(table as *const T as *const std::os::raw::c_void));
+        let code = (self.inner.ExportFunctions)(plugin_id, slot, std::mem::size_of::<T>());
+        if code != 0 {
+            Err(VcmpError::from(code))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn get_plugin_export_table<T: ExportTable>(&self, plugin_id: i32) -> VcmpResult<&'static T> {
+        let exports = self.get_plugin_exports(plugin_id);
+        if exports.exports_ptr.is_null() {
+            return Err(VcmpError::NoSuchEntity);
+        }
+        if exports.size < std::mem::size_of::<T>() {
+            return Err(VcmpError::BufferTooSmall);
+        }
+        if !(exports.exports_ptr as usize).is_multiple_of(std::mem::align_of::<*const T>()) {
+            return Err(VcmpError::ArgumentOutOfBounds);
+        }
+        // 拿到的是存着表指针的槽位, 要再解一次引用
+        let table_ptr = unsafe { *exports.exports_ptr } as *const T;
+        if table_ptr.is_null() {
+            return Err(VcmpError::NoSuchEntity);
+        }
+        if !(table_ptr as usize).is_multiple_of(std::mem::align_of::<T>()) {
+            return Err(VcmpError::ArgumentOutOfBounds);
+        }
+        // 大小和对齐都检查过了, 布局由 ExportTable 的实现者保证
+        Ok(unsafe { &*table_ptr })
+    }
+
+    fn find_plugin_export_table<T: ExportTable>(
+        &self,
+        plugin_name: &str,
+    ) -> VcmpResult<&'static T> {
+        let plugin_id = self
+            .find_plugin(plugin_name)
+            .ok_or(VcmpError::NoSuchEntity)?;
+        self.get_plugin_export_table(plugin_id)
+    }
+
     fn get_plugins(&self) -> Vec<VcmpPluginInfo> {
         (0..self.get_plugin_count())
             .filter_map(|id| self.get_plugin_info(id as i32))
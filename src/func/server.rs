@@ -15,6 +15,10 @@ pub trait ServerMethods {
     fn get_server_password(&self) -> String;
     fn get_gamemode(&self) -> String;
     fn set_max_players(&self, max_player: u32) -> VcmpResult<()>;
+    /// 服务器所在系统的时间
+    ///
+    /// 不叫 `get_time`, 免得和 `QueryEnvironmentWorld::get_time` (游戏里的时间) 撞名
+    fn get_server_time(&self) -> u64;
 }
 
 impl ServerMethods for VcmpFunctions {
@@ -68,11 +72,12 @@ impl ServerMethods for VcmpFunctions {
         let _ = (self.inner.GetServerSettings)(setting_ptr);
         setting
     }
+    /// 当前的服务器名 (server_settings 里的只是启动时的)
     fn get_server_name(&self) -> String {
-        self.server_settings()
-            .server_name()
-            .trim_end_matches("\0")
-            .to_string()
+        let buf = vec![0u8; 1024];
+        let buf_ptr = buf.as_ptr() as *mut i8;
+        let _ = (self.inner.GetServerName)(buf_ptr, 1024);
+        decode_gbk(&buf).trim_end_matches("\0").to_string()
     }
     fn get_server_password(&self) -> String {
         let buf = vec![0u8; 1024];
@@ -94,4 +99,7 @@ impl ServerMethods for VcmpFunctions {
             Ok(())
         }
     }
+    fn get_server_time(&self) -> u64 {
+        (self.inner.GetTime)()
+    }
 }
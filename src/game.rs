@@ -0,0 +1,41 @@
+//! 在 func 之上的一些玩法层面的封装
+//!
+//! 这些东西都不会自己注册回调, 需要在对应的事件里手动调用 `on_xxx`
+
+pub mod announce;
+pub mod class;
+pub mod clock;
+pub mod cutscene;
+pub mod entity;
+pub mod keybind;
+pub mod lookup;
+pub mod map_loader;
+pub mod map_removal;
+pub mod marker;
+pub mod ownership;
+pub mod pickup;
+pub mod race;
+pub mod spectate;
+pub mod team;
+pub mod tween;
+pub mod world;
+
+pub use crate::catalog::GameClock;
+pub use crate::clock::FrameClock;
+pub use announce::AnnounceQueue;
+pub use class::{ClassManager, PlayerClass};
+pub use clock::{ClockSync, DayPhase, EnvironmentScheduler};
+pub use cutscene::{CameraKeyframe, Cutscene, CutsceneEnd, CutscenePlayer};
+pub use entity::GameEntity;
+pub use keybind::KeyBindManager;
+pub use lookup::{PlayerLookup, find_player};
+pub use map_loader::{LoadedMap, MapFile};
+pub use map_removal::{MapObjectRemoval, MapObjectRemovals, RemovedMapObject};
+pub use marker::{BlipDefinition, BlipHandle, MarkerManager};
+pub use ownership::{Owner, OwnershipTracker};
+pub use pickup::{PickupKind, PickupManager, PickupSpec, RespawnPolicy};
+pub use race::{Race, RaceEvent, RaceResult, RaceState, RaceTrack, TrackCheckpoint};
+pub use spectate::{SpectateController, SpectateFilter};
+pub use team::{Team, TeamError, TeamManager, TeamResult};
+pub use tween::{ObjectAnimator, ObjectTween, TweenRepeat, TweenStatus, TweenStep};
+pub use world::{WorldAllocator, WorldInstance};
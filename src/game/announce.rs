@@ -0,0 +1,150 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+use crate::catalog::AnnounceStyle;
+use crate::events::player::PlayerDisconnectEvent;
+use crate::events::server::ServerFrameEvent;
+use crate::func::PlayerMethods;
+use crate::{PlayerId, vcmp_func};
+
+#[derive(Debug, Clone)]
+struct QueuedAnnounce {
+    style: AnnounceStyle,
+    message: String,
+    duration: Duration,
+}
+
+#[derive(Debug, Default)]
+struct PlayerAnnounces {
+    /// 当前正在显示的公告剩余的时间
+    remaining: Option<Duration>,
+    pending: VecDeque<QueuedAnnounce>,
+}
+
+/// 按玩家排队的公告
+///
+/// 连着发的公告会互相覆盖, 这里每个玩家一条队列, 上一条显示够时间之后才发下一条
+///
+/// 需要在 `ServerFrame` 和 `PlayerDisconnect` 里调用对应的方法
+#[derive(Debug)]
+pub struct AnnounceQueue {
+    default_duration: Duration,
+    players: HashMap<PlayerId, PlayerAnnounces>,
+}
+
+impl Default for AnnounceQueue {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(3))
+    }
+}
+
+impl AnnounceQueue {
+    pub fn new(default_duration: Duration) -> Self {
+        Self {
+            default_duration,
+            players: HashMap::new(),
+        }
+    }
+
+    pub fn default_duration(&self) -> Duration {
+        self.default_duration
+    }
+
+    pub fn set_default_duration(&mut self, duration: Duration) {
+        self.default_duration = duration;
+    }
+
+    /// 用默认时长排队一条公告
+    pub fn push(&mut self, player_id: PlayerId, style: AnnounceStyle, message: &str) {
+        self.push_with_duration(player_id, style, message, self.default_duration);
+    }
+
+    /// 排队一条公告, 如果这个玩家当前没有公告在显示就直接发出去
+    pub fn push_with_duration(
+        &mut self,
+        player_id: PlayerId,
+        style: AnnounceStyle,
+        message: &str,
+        duration: Duration,
+    ) {
+        let announces = self.players.entry(player_id).or_default();
+        announces.pending.push_back(QueuedAnnounce {
+            style,
+            message: message.to_string(),
+            duration,
+        });
+        if announces.remaining.is_none() {
+            Self::show_next(player_id, announces);
+        }
+    }
+
+    /// 给所有在线玩家排队
+    pub fn push_to_all(&mut self, style: AnnounceStyle, message: &str) {
+        for player in vcmp_func().get_connected_players() {
+            self.push(player, style, message);
+        }
+    }
+
+    /// 给指定世界的玩家排队
+    pub fn push_to_world(&mut self, world: i32, style: AnnounceStyle, message: &str) {
+        let func = vcmp_func();
+        for player in func.get_connected_players() {
+            if func.get_player_world(player) == world {
+                self.push(player, style, message);
+            }
+        }
+    }
+
+    /// 还没发出去的公告数量 (不包括正在显示的)
+    pub fn pending_count(&self, player_id: PlayerId) -> usize {
+        self.players
+            .get(&player_id)
+            .map(|announces| announces.pending.len())
+            .unwrap_or(0)
+    }
+
+    /// 当前是否有公告在显示
+    pub fn is_showing(&self, player_id: PlayerId) -> bool {
+        self.players
+            .get(&player_id)
+            .is_some_and(|announces| announces.remaining.is_some())
+    }
+
+    /// 丢掉这个玩家还没发出去的公告
+    pub fn clear(&mut self, player_id: PlayerId) {
+        if let Some(announces) = self.players.get_mut(&player_id) {
+            announces.pending.clear();
+        }
+    }
+
+    pub fn on_server_frame(&mut self, event: &ServerFrameEvent) {
+        let elapsed = Duration::from_secs_f32(event.elapsed_time.max(0.0));
+        self.players.retain(|&player_id, announces| {
+            if let Some(remaining) = announces.remaining {
+                if remaining > elapsed {
+                    announces.remaining = Some(remaining - elapsed);
+                    return true;
+                }
+                announces.remaining = None;
+            }
+            Self::show_next(player_id, announces);
+            announces.remaining.is_some()
+        });
+    }
+
+    pub fn on_player_disconnect(&mut self, event: &PlayerDisconnectEvent) {
+        self.players.remove(&event.player_id);
+    }
+
+    fn show_next(player_id: PlayerId, announces: &mut PlayerAnnounces) {
+        while let Some(next) = announces.pending.pop_front() {
+            if vcmp_func()
+                .send_announce(player_id, next.style, &next.message)
+                .is_ok()
+            {
+                announces.remaining = Some(next.duration);
+                return;
+            }
+        }
+    }
+}
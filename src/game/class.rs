@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+
+use crate::events::player::{
+    PlayerDisconnectEvent, PlayerRequestClassEvent, PlayerRequestSpawnEvent, PlayerSpawnEvent,
+};
+use crate::func::{EnvironmentMethods, PlayerMethods};
+use crate::utils::{Color, Vectorf32};
+use crate::{PlayerId, VcmpError, VcmpResult, WeaponId, vcmp_func};
+
+/// 一个可以选择的职业
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayerClass {
+    pub name: String,
+    pub team: i32,
+    pub color: Color,
+    pub skin: i32,
+    pub spawn: Vectorf32,
+    pub angle: f32,
+    /// 前三把会交给 `add_player_class`, 剩下的在出生时给
+    pub loadout: Vec<(WeaponId, i32)>,
+    /// 需要的等级, 配合 `ClassManager::set_rank_provider` 使用
+    pub required_rank: Option<i32>,
+    /// 随便放点什么
+    pub metadata: HashMap<String, String>,
+}
+
+impl PlayerClass {
+    pub fn new(name: &str, team: i32, skin: i32, spawn: Vectorf32) -> Self {
+        Self {
+            name: name.to_string(),
+            team,
+            color: Color::from_rgb(0xFFFFFF, None),
+            skin,
+            spawn,
+            angle: 0.0,
+            loadout: Vec::new(),
+            required_rank: None,
+            metadata: HashMap::new(),
+        }
+    }
+
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn angle(mut self, angle: f32) -> Self {
+        self.angle = angle;
+        self
+    }
+
+    pub fn weapon(mut self, weapon: WeaponId, ammo: i32) -> Self {
+        self.loadout.push((weapon, ammo));
+        self
+    }
+
+    pub fn required_rank(mut self, rank: i32) -> Self {
+        self.required_rank = Some(rank);
+        self
+    }
+
+    pub fn meta(mut self, key: &str, value: &str) -> Self {
+        self.metadata.insert(key.to_string(), value.to_string());
+        self
+    }
+}
+
+type ClassFilter = Box<dyn FnMut(PlayerId, &PlayerClass) -> bool + Send>;
+type RankProvider = Box<dyn FnMut(PlayerId) -> i32 + Send>;
+
+/// 职业和出生选择
+///
+/// `add` 的时候就会调用 `add_player_class`, 之后通过返回的 id 找回职业的信息.
+/// 选择职业时只能记录, 真正拦住受限职业的是 `on_player_request_spawn` 的返回值
+///
+/// 需要在 `PlayerRequestClass` `PlayerRequestSpawn` `PlayerSpawn` `PlayerDisconnect`
+/// 里调用对应的方法
+#[derive(Default)]
+pub struct ClassManager {
+    classes: HashMap<i32, PlayerClass>,
+    selected: HashMap<PlayerId, i32>,
+    rank_provider: Option<RankProvider>,
+    filter: Option<ClassFilter>,
+    denied_message: Option<String>,
+}
+
+impl ClassManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 注册一个职业, 返回服务器分配的 id. 职业满了时返回 `PoolExhausted`
+    pub fn add(&mut self, class: PlayerClass) -> VcmpResult<i32> {
+        let weapon = |index: usize| class.loadout.get(index).copied();
+        let class_id = vcmp_func().add_player_class(
+            class.team,
+            class.color,
+            class.skin,
+            class.spawn,
+            class.angle,
+            weapon(0),
+            weapon(1),
+            weapon(2),
+        );
+        if class_id < 0 {
+            return Err(VcmpError::PoolExhausted);
+        }
+        self.classes.insert(class_id, class);
+        Ok(class_id)
+    }
+
+    pub fn get(&self, class_id: i32) -> Option<&PlayerClass> {
+        self.classes.get(&class_id)
+    }
+
+    pub fn classes(&self) -> impl Iterator<Item = (i32, &PlayerClass)> {
+        self.classes.iter().map(|(&id, class)| (id, class))
+    }
+
+    /// 按名字找职业 id
+    pub fn find(&self, name: &str) -> Option<i32> {
+        self.classes
+            .iter()
+            .find(|(_, class)| class.name.eq_ignore_ascii_case(name))
+            .map(|(&id, _)| id)
+    }
+
+    /// 选择界面的镜头
+    pub fn set_spawn_camera(&self, position: Vectorf32, look_at: Vectorf32) {
+        let func = vcmp_func();
+        func.set_spawn_camera_position(position);
+        func.set_spawn_camera_look_at(look_at);
+    }
+
+    /// 选择界面里人物站的位置
+    pub fn set_spawn_screen_position(&self, position: Vectorf32) {
+        vcmp_func().set_spawn_player_position(position);
+    }
+
+    /// 玩家的等级, 用来检查 `required_rank`
+    pub fn set_rank_provider(&mut self, provider: impl FnMut(PlayerId) -> i32 + Send + 'static) {
+        self.rank_provider = Some(Box::new(provider));
+    }
+
+    /// 额外的限制, 返回 false 不让选
+    pub fn set_filter(
+        &mut self,
+        filter: impl FnMut(PlayerId, &PlayerClass) -> bool + Send + 'static,
+    ) {
+        self.filter = Some(Box::new(filter));
+    }
+
+    /// 被拒绝时发给玩家的消息, `{class}` 会被替换成职业名
+    pub fn set_denied_message(&mut self, message: Option<&str>) {
+        self.denied_message = message.map(|message| message.to_string());
+    }
+
+    /// 玩家当前选中的职业
+    pub fn selected(&self, player_id: PlayerId) -> Option<(i32, &PlayerClass)> {
+        let class_id = *self.selected.get(&player_id)?;
+        Some((class_id, self.classes.get(&class_id)?))
+    }
+
+    /// 检查等级和额外限制, 不认识的职业总是允许
+    pub fn can_select(&mut self, player_id: PlayerId, class_id: i32) -> bool {
+        let Some(class) = self.classes.get(&class_id) else {
+            return true;
+        };
+        if let Some(required) = class.required_rank {
+            let rank = self
+                .rank_provider
+                .as_mut()
+                .map(|provider| provider(player_id))
+                .unwrap_or(0);
+            if rank < required {
+                return false;
+            }
+        }
+        self.filter
+            .as_mut()
+            .is_none_or(|filter| filter(player_id, class))
+    }
+
+    /// 记录选中的职业, 返回是否允许
+    ///
+    /// 事件里的 `class_id` 其实是翻页的偏移, 真正的职业用 `get_player_class` 取
+    pub fn on_player_request_class(&mut self, event: &PlayerRequestClassEvent) -> bool {
+        let class_id = vcmp_func().get_player_class(event.player_id);
+        self.selected.insert(event.player_id, class_id);
+        self.can_select(event.player_id, class_id)
+    }
+
+    /// 选中的职业不允许时返回 false, 应该拒绝出生
+    pub fn on_player_request_spawn(&mut self, event: &PlayerRequestSpawnEvent) -> bool {
+        let Some(&class_id) = self.selected.get(&event.player_id) else {
+            return true;
+        };
+        if self.can_select(event.player_id, class_id) {
+            return true;
+        }
+        if let (Some(message), Some(class)) = (&self.denied_message, self.classes.get(&class_id)) {
+            let _ = vcmp_func().send_client_message(
+                event.player_id,
+                Color::from_rgb(0xFF4040, None),
+                &message.replace("{class}", &class.name),
+            );
+        }
+        false
+    }
+
+    /// 补上 `add_player_class` 放不下的武器
+    pub fn on_player_spawn(&mut self, event: &PlayerSpawnEvent) {
+        let Some((_, class)) = self.selected(event.player_id) else {
+            return;
+        };
+        let func = vcmp_func();
+        for &(weapon, ammo) in class.loadout.iter().skip(3) {
+            let _ = func.give_player_weapon(event.player_id, weapon, ammo);
+        }
+    }
+
+    pub fn on_player_disconnect(&mut self, event: &PlayerDisconnectEvent) {
+        self.selected.remove(&event.player_id);
+    }
+}
@@ -0,0 +1,259 @@
+use std::time::{Duration, SystemTime};
+
+use crate::catalog::{GameClock, Weather};
+use crate::clock::FrameClock;
+use crate::events::server::ServerFrameEvent;
+use crate::func::{QueryEnvironmentWorld, SetEnvironmentWorld};
+use crate::vcmp_func;
+
+const MINUTES_PER_DAY: i32 = 24 * 60;
+
+/*
+    EnvironmentScheduler
+*/
+
+/// 游戏时间怎么走
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClockSync {
+    /// 不管, 交给服务器自己的 time rate
+    Server,
+    /// 游戏内一整天对应的现实时长, 会换算成 time rate
+    DayLength(Duration),
+    /// 和现实时间同步, `utc_offset_minutes` 是时区相对 UTC 的分钟数
+    RealTime { utc_offset_minutes: i32 },
+}
+
+/// 白天还是晚上
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DayPhase {
+    Day,
+    Night,
+}
+
+/// 昼夜和天气的调度
+///
+/// 需要在 `ServerFrame` 里调用 `on_server_frame`
+pub struct EnvironmentScheduler {
+    frame: FrameClock,
+    sync: ClockSync,
+    sync_applied: bool,
+    last_synced: Option<GameClock>,
+    day_start: GameClock,
+    night_start: GameClock,
+    phase: Option<DayPhase>,
+    on_phase_change: Option<Box<dyn FnMut(DayPhase, GameClock) + Send>>,
+    weather_cycle: Vec<(Weather, Duration)>,
+    weather_index: usize,
+    weather_elapsed: Duration,
+    weather_applied: bool,
+}
+
+impl Default for EnvironmentScheduler {
+    fn default() -> Self {
+        Self::new(ClockSync::Server)
+    }
+}
+
+impl EnvironmentScheduler {
+    pub fn new(sync: ClockSync) -> Self {
+        Self {
+            frame: FrameClock::new(),
+            sync,
+            sync_applied: false,
+            last_synced: None,
+            day_start: GameClock::new(6, 0),
+            night_start: GameClock::new(20, 0),
+            phase: None,
+            on_phase_change: None,
+            weather_cycle: Vec::new(),
+            weather_index: 0,
+            weather_elapsed: Duration::ZERO,
+            weather_applied: false,
+        }
+    }
+
+    pub fn sync(&self) -> ClockSync {
+        self.sync
+    }
+
+    /// 下一帧生效
+    pub fn set_sync(&mut self, sync: ClockSync) {
+        self.sync = sync;
+        self.sync_applied = false;
+        self.last_synced = None;
+    }
+
+    /// 白天和晚上开始的时间, 默认 06:00 和 20:00
+    pub fn set_day_hours(&mut self, day_start: GameClock, night_start: GameClock) {
+        self.day_start = day_start;
+        self.night_start = night_start;
+    }
+
+    pub fn phase_of(&self, clock: GameClock) -> DayPhase {
+        if clock.is_between(self.day_start, self.night_start) {
+            DayPhase::Day
+        } else {
+            DayPhase::Night
+        }
+    }
+
+    /// 最近一帧看到的昼夜
+    pub fn phase(&self) -> Option<DayPhase> {
+        self.phase
+    }
+
+    /// 昼夜切换时的回调, 第一次看到时间的时候也会调用一次
+    pub fn on_phase_change(&mut self, callback: impl FnMut(DayPhase, GameClock) + Send + 'static) {
+        self.on_phase_change = Some(Box::new(callback));
+    }
+
+    /// 按顺序轮换天气, 每个天气持续对应的现实时长, 放完了从头开始
+    pub fn set_weather_cycle(&mut self, cycle: Vec<(Weather, Duration)>) {
+        self.weather_cycle = cycle;
+        self.weather_index = 0;
+        self.weather_elapsed = Duration::ZERO;
+        self.weather_applied = false;
+    }
+
+    pub fn clear_weather_cycle(&mut self) {
+        self.set_weather_cycle(Vec::new());
+    }
+
+    /// 轮换中当前的天气
+    pub fn current_weather(&self) -> Option<Weather> {
+        self.weather_cycle
+            .get(self.weather_index)
+            .map(|(weather, _)| *weather)
+    }
+
+    pub fn on_server_frame(&mut self, event: &ServerFrameEvent) {
+        let elapsed = self.frame.advance(event);
+        let func = vcmp_func();
+
+        let clock = match self.sync {
+            ClockSync::Server => func.get_game_clock(),
+            ClockSync::DayLength(length) => {
+                if !self.sync_applied {
+                    let rate = length.as_millis() / MINUTES_PER_DAY as u128;
+                    func.set_time_rate(rate.clamp(1, i32::MAX as u128) as i32);
+                    self.sync_applied = true;
+                }
+                func.get_game_clock()
+            }
+            ClockSync::RealTime { utc_offset_minutes } => {
+                let clock = GameClock::from_system_time(SystemTime::now(), utc_offset_minutes);
+                if self.last_synced != Some(clock) {
+                    func.set_game_clock(clock);
+                    self.last_synced = Some(clock);
+                }
+                clock
+            }
+        };
+
+        let phase = self.phase_of(clock);
+        if self.phase != Some(phase) {
+            self.phase = Some(phase);
+            if let Some(callback) = self.on_phase_change.as_mut() {
+                callback(phase, clock);
+            }
+        }
+
+        self.tick_weather(elapsed);
+    }
+
+    fn tick_weather(&mut self, elapsed: Duration) {
+        if self.weather_cycle.is_empty() {
+            return;
+        }
+        if !self.weather_applied {
+            vcmp_func().set_weather(self.weather_cycle[self.weather_index].0);
+            self.weather_applied = true;
+        }
+        if self.advance_weather(elapsed) {
+            vcmp_func().set_weather(self.weather_cycle[self.weather_index].0);
+        }
+    }
+
+    /// 推进轮换, 换了天气时返回 true
+    fn advance_weather(&mut self, elapsed: Duration) -> bool {
+        if self.weather_cycle.is_empty() {
+            return false;
+        }
+        self.weather_elapsed += elapsed;
+        let mut changed = false;
+        loop {
+            let duration = self.weather_cycle[self.weather_index].1;
+            // 时长为 0 的天气直接跳过, 防止死循环
+            if duration.is_zero() && self.weather_cycle.iter().all(|(_, d)| d.is_zero()) {
+                break;
+            }
+            if self.weather_elapsed < duration {
+                break;
+            }
+            self.weather_elapsed -= duration;
+            self.weather_index = (self.weather_index + 1) % self.weather_cycle.len();
+            changed = true;
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn day_phase() {
+        let mut scheduler = EnvironmentScheduler::default();
+        assert_eq!(scheduler.phase_of(GameClock::new(6, 0)), DayPhase::Day);
+        assert_eq!(scheduler.phase_of(GameClock::new(19, 59)), DayPhase::Day);
+        assert_eq!(scheduler.phase_of(GameClock::new(20, 0)), DayPhase::Night);
+        assert_eq!(scheduler.phase_of(GameClock::new(3, 0)), DayPhase::Night);
+        // 白天跨过午夜
+        scheduler.set_day_hours(GameClock::new(22, 0), GameClock::new(4, 0));
+        assert_eq!(scheduler.phase_of(GameClock::new(1, 0)), DayPhase::Day);
+        assert_eq!(scheduler.phase_of(GameClock::NOON), DayPhase::Night);
+    }
+
+    #[test]
+    fn weather_cycle() {
+        let mut scheduler = EnvironmentScheduler::default();
+        assert!(!scheduler.advance_weather(secs(100)));
+        assert_eq!(scheduler.current_weather(), None);
+
+        scheduler.set_weather_cycle(vec![
+            (Weather::Sunny, secs(10)),
+            (Weather::Rainy, secs(5)),
+            (Weather::Foggy, Duration::ZERO),
+            (Weather::Cloudy, secs(20)),
+        ]);
+        assert_eq!(scheduler.current_weather(), Some(Weather::Sunny));
+        assert!(!scheduler.advance_weather(secs(9)));
+        assert!(scheduler.advance_weather(secs(1)));
+        assert_eq!(scheduler.current_weather(), Some(Weather::Rainy));
+        // 时长为 0 的天气直接跳过
+        assert!(scheduler.advance_weather(secs(5)));
+        assert_eq!(scheduler.current_weather(), Some(Weather::Cloudy));
+        // 一帧跨过好几个, 放完了从头开始, 剩下的时间留给下一个
+        assert!(scheduler.advance_weather(secs(20 + 10 + 3)));
+        assert_eq!(scheduler.current_weather(), Some(Weather::Rainy));
+        assert!(!scheduler.advance_weather(secs(1)));
+        assert!(scheduler.advance_weather(secs(1)));
+        assert_eq!(scheduler.current_weather(), Some(Weather::Cloudy));
+    }
+
+    #[test]
+    fn all_zero_weather_cycle_does_not_spin() {
+        let mut scheduler = EnvironmentScheduler::default();
+        scheduler.set_weather_cycle(vec![
+            (Weather::Sunny, Duration::ZERO),
+            (Weather::Rainy, Duration::ZERO),
+        ]);
+        assert!(!scheduler.advance_weather(secs(10)));
+        assert_eq!(scheduler.current_weather(), Some(Weather::Sunny));
+    }
+}
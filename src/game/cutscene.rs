@@ -0,0 +1,316 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::catalog::KeyCode;
+use crate::events::player::{PlayerDeathEvent, PlayerDisconnectEvent, PlayerKeyBindDownEvent};
+use crate::events::server::ServerFrameEvent;
+use crate::func::PlayerMethods;
+use crate::game::{FrameClock, KeyBindManager};
+use crate::utils::Vectorf32;
+use crate::{PlayerId, VcmpError, VcmpResult, vcmp_func};
+
+const SKIP_BIND: &str = "cutscene_skip";
+
+/// 过场动画里的一个镜头
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraKeyframe {
+    pub position: Vectorf32,
+    pub look_at: Vectorf32,
+    /// 停留多久再切到下一个镜头
+    pub duration: Duration,
+    /// 为 true 时朝向从上一个镜头的 look_at 慢慢转过来, 位置还是直接切过去
+    pub pan: bool,
+}
+
+impl CameraKeyframe {
+    pub fn new(position: Vectorf32, look_at: Vectorf32, duration: Duration) -> Self {
+        Self {
+            position,
+            look_at,
+            duration,
+            pan: false,
+        }
+    }
+
+    pub fn pan(mut self) -> Self {
+        self.pan = true;
+        self
+    }
+}
+
+/// 一段过场动画
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cutscene {
+    pub keyframes: Vec<CameraKeyframe>,
+    /// 能不能用跳过键跳过, 默认可以
+    pub skippable: bool,
+}
+
+impl Default for Cutscene {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Cutscene {
+    pub fn new() -> Self {
+        Self {
+            keyframes: Vec::new(),
+            skippable: true,
+        }
+    }
+
+    pub fn keyframe(mut self, keyframe: CameraKeyframe) -> Self {
+        self.keyframes.push(keyframe);
+        self
+    }
+
+    pub fn skippable(mut self, skippable: bool) -> Self {
+        self.skippable = skippable;
+        self
+    }
+
+    pub fn total_duration(&self) -> Duration {
+        self.keyframes
+            .iter()
+            .map(|keyframe| keyframe.duration)
+            .sum()
+    }
+}
+
+/// 过场动画为什么结束
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CutsceneEnd {
+    Finished,
+    /// 玩家按了跳过键
+    Skipped,
+    /// 调用了 `stop` 或者开始了另一段动画
+    Stopped,
+    Died,
+    Disconnected,
+}
+
+type CompletionHandler = Box<dyn FnMut(PlayerId, CutsceneEnd) + Send>;
+
+struct SceneRun {
+    cutscene: Cutscene,
+    on_complete: Option<CompletionHandler>,
+    /// 还在看的人数, 为 0 时删掉
+    viewers: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Viewer {
+    scene: u32,
+    keyframe: usize,
+    /// 当前镜头结束的时间
+    next_at: Duration,
+}
+
+/// 播放过场动画
+///
+/// 每个玩家独立推进镜头. 不管怎么结束都会调用 `restore_camera`
+/// (断开的玩家除外, 没有镜头可以恢复), 然后调用完成回调
+///
+/// 需要在 `ServerFrame` `PlayerKeyBindDown` `PlayerDeath` `PlayerDisconnect`
+/// 里调用对应的方法
+#[derive(Default)]
+pub struct CutscenePlayer {
+    clock: FrameClock,
+    next_scene: u32,
+    scenes: HashMap<u32, SceneRun>,
+    viewers: HashMap<PlayerId, Viewer>,
+}
+
+impl CutscenePlayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 用 `KeyBindManager` 注册跳过键
+    pub fn register_skip_key(&self, binds: &mut KeyBindManager, key: KeyCode) -> VcmpResult<()> {
+        binds.register(SKIP_BIND, &[key], false)?;
+        Ok(())
+    }
+
+    /// 给这些玩家播放, 正在看别的动画的玩家会先以 `Stopped` 结束
+    ///
+    /// 没有镜头时返回 `ArgumentOutOfBounds`
+    pub fn play(&mut self, players: &[PlayerId], cutscene: Cutscene) -> VcmpResult<()> {
+        self.start(players, cutscene, None)
+    }
+
+    /// 和 `play` 一样, 每个玩家看完 (或者被打断) 时调用 `on_complete`
+    pub fn play_with(
+        &mut self,
+        players: &[PlayerId],
+        cutscene: Cutscene,
+        on_complete: impl FnMut(PlayerId, CutsceneEnd) + Send + 'static,
+    ) -> VcmpResult<()> {
+        self.start(players, cutscene, Some(Box::new(on_complete)))
+    }
+
+    pub fn is_playing(&self, player_id: PlayerId) -> bool {
+        self.viewers.contains_key(&player_id)
+    }
+
+    /// 结束这个玩家的动画, 没在看时返回 false
+    pub fn stop(&mut self, player_id: PlayerId) -> bool {
+        self.finish(player_id, CutsceneEnd::Stopped)
+    }
+
+    pub fn stop_all(&mut self) {
+        let players: Vec<PlayerId> = self.viewers.keys().copied().collect();
+        for player_id in players {
+            self.finish(player_id, CutsceneEnd::Stopped);
+        }
+    }
+
+    /// 跳过, 动画不能跳过或者没在看时返回 false
+    pub fn skip(&mut self, player_id: PlayerId) -> bool {
+        let skippable = self
+            .viewers
+            .get(&player_id)
+            .and_then(|viewer| self.scenes.get(&viewer.scene))
+            .is_some_and(|scene| scene.cutscene.skippable);
+        skippable && self.finish(player_id, CutsceneEnd::Skipped)
+    }
+
+    pub fn on_server_frame(&mut self, event: &ServerFrameEvent) {
+        self.clock.advance(event);
+        let now = self.clock.now();
+        let due: Vec<PlayerId> = self
+            .viewers
+            .iter()
+            .filter(|(_, viewer)| viewer.next_at <= now)
+            .map(|(&player_id, _)| player_id)
+            .collect();
+        for player_id in due {
+            self.advance(player_id);
+        }
+    }
+
+    /// 处理 `register_skip_key` 注册的按键, 不是跳过键时返回 false
+    pub fn on_key_bind_down(
+        &mut self,
+        event: &PlayerKeyBindDownEvent,
+        binds: &KeyBindManager,
+    ) -> bool {
+        if binds.name_of(event.bind_id) != Some(SKIP_BIND) {
+            return false;
+        }
+        self.skip(event.player_id);
+        true
+    }
+
+    pub fn on_player_death(&mut self, event: &PlayerDeathEvent) {
+        self.finish(event.player_id, CutsceneEnd::Died);
+    }
+
+    pub fn on_player_disconnect(&mut self, event: &PlayerDisconnectEvent) {
+        self.finish(event.player_id, CutsceneEnd::Disconnected);
+    }
+
+    fn start(
+        &mut self,
+        players: &[PlayerId],
+        cutscene: Cutscene,
+        on_complete: Option<CompletionHandler>,
+    ) -> VcmpResult<()> {
+        let Some(first) = cutscene.keyframes.first().copied() else {
+            return Err(VcmpError::ArgumentOutOfBounds);
+        };
+        let scene = self.next_scene;
+        self.next_scene += 1;
+        self.scenes.insert(
+            scene,
+            SceneRun {
+                cutscene,
+                on_complete,
+                viewers: 0,
+            },
+        );
+        let next_at = self.clock.now() + first.duration;
+        let mut players = players.to_vec();
+        players.sort();
+        players.dedup();
+        for player_id in players {
+            self.finish(player_id, CutsceneEnd::Stopped);
+            if Self::show(player_id, &first, None).is_err() {
+                continue;
+            }
+            self.viewers.insert(
+                player_id,
+                Viewer {
+                    scene,
+                    keyframe: 0,
+                    next_at,
+                },
+            );
+            if let Some(run) = self.scenes.get_mut(&scene) {
+                run.viewers += 1;
+            }
+        }
+        if self.scenes.get(&scene).is_some_and(|run| run.viewers == 0) {
+            self.scenes.remove(&scene);
+        }
+        Ok(())
+    }
+
+    /// 切到下一个镜头, 没有了就结束
+    fn advance(&mut self, player_id: PlayerId) {
+        let Some(viewer) = self.viewers.get_mut(&player_id) else {
+            return;
+        };
+        let Some(run) = self.scenes.get(&viewer.scene) else {
+            self.finish(player_id, CutsceneEnd::Stopped);
+            return;
+        };
+        let previous = run.cutscene.keyframes[viewer.keyframe];
+        let Some(&keyframe) = run.cutscene.keyframes.get(viewer.keyframe + 1) else {
+            self.finish(player_id, CutsceneEnd::Finished);
+            return;
+        };
+        viewer.keyframe += 1;
+        viewer.next_at += keyframe.duration;
+        if Self::show(player_id, &keyframe, Some(&previous)).is_err() {
+            self.finish(player_id, CutsceneEnd::Stopped);
+        }
+    }
+
+    fn show(
+        player_id: PlayerId,
+        keyframe: &CameraKeyframe,
+        previous: Option<&CameraKeyframe>,
+    ) -> VcmpResult<()> {
+        let func = vcmp_func();
+        match previous {
+            Some(previous) if keyframe.pan => {
+                func.set_camera_position(player_id, keyframe.position, previous.look_at)?;
+                let time = keyframe.duration.as_millis().min(u32::MAX as u128) as u32;
+                func.interpolate_camera_look_at(player_id, keyframe.look_at, time)
+            }
+            _ => func.set_camera_position(player_id, keyframe.position, keyframe.look_at),
+        }
+    }
+
+    /// 恢复镜头并调用回调, 没在看时返回 false
+    fn finish(&mut self, player_id: PlayerId, reason: CutsceneEnd) -> bool {
+        let Some(viewer) = self.viewers.remove(&player_id) else {
+            return false;
+        };
+        if reason != CutsceneEnd::Disconnected {
+            let _ = vcmp_func().restore_camera(player_id);
+        }
+        if let Some(run) = self.scenes.get_mut(&viewer.scene) {
+            if let Some(handler) = run.on_complete.as_mut() {
+                handler(player_id, reason);
+            }
+            run.viewers = run.viewers.saturating_sub(1);
+            if run.viewers == 0 {
+                self.scenes.remove(&viewer.scene);
+            }
+        }
+        true
+    }
+}
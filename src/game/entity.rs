@@ -0,0 +1,55 @@
+use crate::func::{
+    CheckPointMethods, MarkerMethods, ObjectMethods, PickupMethods, QueryVehicle, SetVehicle,
+    VehicleMethods,
+};
+use crate::{CheckpointId, MarkerId, ObjectId, VcmpError, VcmpResult, VehicleId, vcmp_func};
+
+/// 除了玩家以外的各种实体
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameEntity {
+    Vehicle(VehicleId),
+    Object(ObjectId),
+    Pickup(i32),
+    Checkpoint(CheckpointId),
+    Marker(MarkerId),
+}
+
+impl GameEntity {
+    pub fn is_alive(&self) -> bool {
+        let func = vcmp_func();
+        match *self {
+            GameEntity::Vehicle(id) => func.is_vehicle_alive(id),
+            GameEntity::Object(id) => func.is_object_alive(id),
+            GameEntity::Pickup(id) => func.is_pickup_alive(id),
+            GameEntity::Checkpoint(id) => func.is_checkpoint_alive(id),
+            GameEntity::Marker(id) => func.is_marker_alive(id),
+        }
+    }
+
+    /// 小地图图标没法换世界, 会返回 `RequestDenied`
+    pub fn set_world(&self, world: i32) -> VcmpResult<()> {
+        let func = vcmp_func();
+        match *self {
+            GameEntity::Vehicle(id) => func.set_vehicle_world(id, world),
+            GameEntity::Object(id) => func.set_object_world(id, world),
+            GameEntity::Pickup(id) => func.set_pickup_world(id, world),
+            GameEntity::Checkpoint(id) => func.set_checkpoint_world(id, world),
+            GameEntity::Marker(_) => Err(VcmpError::RequestDenied),
+        }
+    }
+
+    /// 删掉这个实体, 已经不存在的话什么都不做
+    pub fn destroy(&self) -> VcmpResult<()> {
+        if !self.is_alive() {
+            return Ok(());
+        }
+        let func = vcmp_func();
+        match *self {
+            GameEntity::Vehicle(id) => func.delete_vehicle(id),
+            GameEntity::Object(id) => func.delete_object(id),
+            GameEntity::Pickup(id) => func.delete_pickup(id),
+            GameEntity::Checkpoint(id) => func.delete_checkpoint(id),
+            GameEntity::Marker(id) => func.destroy_marker(id),
+        }
+    }
+}
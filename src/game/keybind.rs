@@ -0,0 +1,256 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::catalog::KeyCode;
+use crate::events::player::{PlayerDisconnectEvent, PlayerKeyBindDownEvent, PlayerKeyBindUpEvent};
+use crate::events::server::ServerFrameEvent;
+use crate::func::KeybindMethods;
+use crate::game::FrameClock;
+use crate::{PlayerId, VcmpError, VcmpResult, vcmp_func};
+
+type DownHandler = Box<dyn FnMut(PlayerId) + Send>;
+/// 参数是玩家和按住的时长
+type UpHandler = Box<dyn FnMut(PlayerId, Duration) + Send>;
+
+struct NamedBind {
+    keys: [KeyCode; 3],
+    release: bool,
+    /// 服务器那边的槽位, 被 `remove_all_key_binds` 清掉之后为 None
+    slot: Option<i32>,
+    on_down: Option<DownHandler>,
+    on_up: Option<UpHandler>,
+}
+
+/// 按名字管理按键绑定
+///
+/// 按下时记录时间, 松开时把按住的时长传给 handler. 只有 `release` 为 true 的绑定
+/// 才会收到 `PlayerKeyBindUp`
+///
+/// 脚本重载时服务器上的绑定可能已经被 `remove_all_key_binds` 清掉了, 这时调用
+/// `restore` 按原来的名字和按键重新注册, 槽位可能会变
+///
+/// 需要在 `PlayerKeyBindDown` `PlayerKeyBindUp` `ServerFrame` `PlayerDisconnect`
+/// 里调用对应的方法
+#[derive(Default)]
+pub struct KeyBindManager {
+    clock: FrameClock,
+    binds: HashMap<String, NamedBind>,
+    slots: HashMap<i32, String>,
+    held: HashMap<(PlayerId, i32), Duration>,
+}
+
+impl KeyBindManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 注册一个绑定, 最多 3 个按键. 同名的绑定会被替换, handler 保留
+    ///
+    /// 没有空闲槽位时返回 `PoolExhausted`, 这时名字和按键还会留着, 之后可以 `restore`
+    pub fn register(&mut self, name: &str, keys: &[KeyCode], release: bool) -> VcmpResult<i32> {
+        if keys.is_empty() || keys.len() > 3 {
+            return Err(VcmpError::ArgumentOutOfBounds);
+        }
+        let mut padded = [KeyCode::None; 3];
+        padded[..keys.len()].copy_from_slice(keys);
+
+        let (on_down, on_up) = match self.remove_slot(name) {
+            Some(old) => (old.on_down, old.on_up),
+            None => (None, None),
+        };
+        let mut bind = NamedBind {
+            keys: padded,
+            release,
+            slot: None,
+            on_down,
+            on_up,
+        };
+        let result = Self::register_native(&bind);
+        if let Ok(slot) = result {
+            bind.slot = Some(slot);
+            self.slots.insert(slot, name.to_string());
+        }
+        self.binds.insert(name.to_string(), bind);
+        result
+    }
+
+    /// 按下时调用, 不存在的绑定返回 false
+    pub fn on_down(&mut self, name: &str, handler: impl FnMut(PlayerId) + Send + 'static) -> bool {
+        match self.binds.get_mut(name) {
+            Some(bind) => {
+                bind.on_down = Some(Box::new(handler));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 松开时调用, 参数里有按住的时长. 不存在的绑定返回 false
+    pub fn on_up(
+        &mut self,
+        name: &str,
+        handler: impl FnMut(PlayerId, Duration) + Send + 'static,
+    ) -> bool {
+        match self.binds.get_mut(name) {
+            Some(bind) => {
+                bind.on_up = Some(Box::new(handler));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 删掉绑定和服务器上的槽位
+    pub fn unregister(&mut self, name: &str) -> bool {
+        self.remove_slot(name).is_some()
+    }
+
+    pub fn slot(&self, name: &str) -> Option<i32> {
+        self.binds.get(name)?.slot
+    }
+
+    pub fn name_of(&self, slot: i32) -> Option<&str> {
+        self.slots.get(&slot).map(String::as_str)
+    }
+
+    pub fn keys(&self, name: &str) -> Option<Vec<KeyCode>> {
+        let bind = self.binds.get(name)?;
+        Some(
+            bind.keys
+                .into_iter()
+                .filter(|key| *key != KeyCode::None)
+                .collect(),
+        )
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.binds.keys().map(String::as_str).collect();
+        names.sort();
+        names
+    }
+
+    /// 玩家是否正按着这个绑定
+    pub fn is_held(&self, player_id: PlayerId, name: &str) -> bool {
+        self.held_for(player_id, name).is_some()
+    }
+
+    /// 玩家已经按了多久
+    pub fn held_for(&self, player_id: PlayerId, name: &str) -> Option<Duration> {
+        let slot = self.slot(name)?;
+        let since = self.held.get(&(player_id, slot))?;
+        Some(self.clock.now().saturating_sub(*since))
+    }
+
+    /// 调用 `remove_all_key_binds`, 只保留名字 / 按键 / handler, 之后可以 `restore`
+    pub fn clear(&mut self) {
+        vcmp_func().remove_all_key_binds();
+        self.forget_slots();
+    }
+
+    /// 服务器上的绑定被清掉之后重新注册所有绑定, 返回注册失败的名字
+    ///
+    /// 不会调用 `remove_all_key_binds`, 需要的话先 `clear`
+    pub fn restore(&mut self) -> Vec<String> {
+        self.forget_slots();
+        let mut names: Vec<String> = self.binds.keys().cloned().collect();
+        names.sort();
+        let mut failed = Vec::new();
+        for name in names {
+            let Some(bind) = self.binds.get_mut(&name) else {
+                continue;
+            };
+            match Self::register_native(bind) {
+                Ok(slot) => {
+                    bind.slot = Some(slot);
+                    self.slots.insert(slot, name);
+                }
+                Err(_) => failed.push(name),
+            }
+        }
+        failed
+    }
+
+    /// 不是这里管理的绑定返回 false
+    pub fn on_key_bind_down(&mut self, event: &PlayerKeyBindDownEvent) -> bool {
+        let Some(name) = self.slots.get(&event.bind_id) else {
+            return false;
+        };
+        self.held
+            .insert((event.player_id, event.bind_id), self.clock.now());
+        if let Some(handler) = self
+            .binds
+            .get_mut(name)
+            .and_then(|bind| bind.on_down.as_mut())
+        {
+            handler(event.player_id);
+        }
+        true
+    }
+
+    /// 不是这里管理的绑定返回 false. 没收到按下时时长为 0
+    pub fn on_key_bind_up(&mut self, event: &PlayerKeyBindUpEvent) -> bool {
+        let Some(name) = self.slots.get(&event.bind_id) else {
+            return false;
+        };
+        let held = self
+            .held
+            .remove(&(event.player_id, event.bind_id))
+            .map(|since| self.clock.now().saturating_sub(since))
+            .unwrap_or_default();
+        if let Some(handler) = self
+            .binds
+            .get_mut(name)
+            .and_then(|bind| bind.on_up.as_mut())
+        {
+            handler(event.player_id, held);
+        }
+        true
+    }
+
+    pub fn on_server_frame(&mut self, event: &ServerFrameEvent) {
+        self.clock.advance(event);
+    }
+
+    pub fn on_player_disconnect(&mut self, event: &PlayerDisconnectEvent) {
+        self.held
+            .retain(|(player_id, _), _| *player_id != event.player_id);
+    }
+
+    fn register_native(bind: &NamedBind) -> VcmpResult<i32> {
+        let func = vcmp_func();
+        // register_key_bind 自己不检查槽位, 用完了会拿到 -1
+        if func.get_key_bind_unused_slot() < 0 {
+            return Err(VcmpError::PoolExhausted);
+        }
+        let [key, key2, key3] = bind.keys.map(i32::from);
+        let registered = func.register_key_bind(
+            bind.release,
+            key,
+            (key2 != 0).then_some(key2),
+            (key3 != 0).then_some(key3),
+        );
+        if registered.slot < 0 {
+            return Err(VcmpError::PoolExhausted);
+        }
+        Ok(registered.slot)
+    }
+
+    /// 删掉服务器上的槽位, 返回原来的绑定
+    fn remove_slot(&mut self, name: &str) -> Option<NamedBind> {
+        let mut bind = self.binds.remove(name)?;
+        if let Some(slot) = bind.slot.take() {
+            vcmp_func().remove_key_bind(slot);
+            self.slots.remove(&slot);
+            self.held.retain(|(_, held_slot), _| *held_slot != slot);
+        }
+        Some(bind)
+    }
+
+    fn forget_slots(&mut self) {
+        self.slots.clear();
+        self.held.clear();
+        for bind in self.binds.values_mut() {
+            bind.slot = None;
+        }
+    }
+}
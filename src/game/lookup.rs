@@ -0,0 +1,127 @@
+use crate::func::PlayerMethods;
+use crate::{PlayerId, vcmp_func};
+
+/// 按名字找玩家的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlayerLookup {
+    Found(PlayerId),
+    NotFound,
+    /// 有多个玩家都符合, 需要输入得更具体一些
+    Ambiguous(Vec<PlayerId>),
+}
+
+impl PlayerLookup {
+    pub fn found(&self) -> Option<PlayerId> {
+        match self {
+            PlayerLookup::Found(player) => Some(*player),
+            _ => None,
+        }
+    }
+
+    fn from_matches(matches: Vec<PlayerId>) -> Option<Self> {
+        match matches.as_slice() {
+            [] => None,
+            [player] => Some(PlayerLookup::Found(*player)),
+            _ => Some(PlayerLookup::Ambiguous(matches)),
+        }
+    }
+}
+
+/// 在给定的 (id, 名字) 里按名字找玩家
+///
+/// 依次尝试: 不分大小写的完全一致, 前缀, 包含; 每一步只要有结果就不会继续往下
+pub fn match_player_name(query: &str, players: &[(PlayerId, String)]) -> PlayerLookup {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return PlayerLookup::NotFound;
+    }
+    let players: Vec<(PlayerId, String)> = players
+        .iter()
+        .map(|(player, name)| (*player, name.to_lowercase()))
+        .collect();
+    let rules: [&dyn Fn(&str) -> bool; 3] = [
+        &|name| name == query,
+        &|name| name.starts_with(&query),
+        &|name| name.contains(&query),
+    ];
+    for rule in rules {
+        let matches = players
+            .iter()
+            .filter(|(_, name)| rule(name))
+            .map(|(player, _)| *player)
+            .collect();
+        if let Some(lookup) = PlayerLookup::from_matches(matches) {
+            return lookup;
+        }
+    }
+    PlayerLookup::NotFound
+}
+
+/// 按名字找在线的玩家
+///
+/// 先用服务器的 `GetPlayerIdFromName` 找完全一致的, 找不到再用 `match_player_name`
+pub fn find_player(query: &str) -> PlayerLookup {
+    let func = vcmp_func();
+    if let Some(player) = func.get_player_id_from_name(query.trim()) {
+        return PlayerLookup::Found(player);
+    }
+    let players: Vec<(PlayerId, String)> = func
+        .get_connected_players()
+        .into_iter()
+        .map(|player| (player, func.get_player_name(player)))
+        .collect();
+    match_player_name(query, &players)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn players() -> Vec<(PlayerId, String)> {
+        vec![
+            (0, "Tommy".to_string()),
+            (1, "TommyV".to_string()),
+            (2, "Lance".to_string()),
+            (3, "Ken_Rosenberg".to_string()),
+            (4, "Rosa".to_string()),
+        ]
+    }
+
+    #[test]
+    fn exact_match_wins_over_prefix() {
+        assert_eq!(
+            match_player_name("tommy", &players()),
+            PlayerLookup::Found(0)
+        );
+        assert_eq!(
+            match_player_name("  TOMMYV ", &players()),
+            PlayerLookup::Found(1)
+        );
+    }
+
+    #[test]
+    fn prefix_then_substring() {
+        assert_eq!(match_player_name("lan", &players()), PlayerLookup::Found(2));
+        // 前缀只匹配到 Rosa, 不会再去看包含 "ros" 的 Ken_Rosenberg
+        assert_eq!(match_player_name("ros", &players()), PlayerLookup::Found(4));
+        assert_eq!(
+            match_player_name("berg", &players()),
+            PlayerLookup::Found(3)
+        );
+    }
+
+    #[test]
+    fn ambiguous_and_not_found() {
+        assert_eq!(
+            match_player_name("o", &players()),
+            PlayerLookup::Ambiguous(vec![0, 1, 3, 4])
+        );
+        assert_eq!(
+            match_player_name("sonny", &players()),
+            PlayerLookup::NotFound
+        );
+        assert_eq!(match_player_name("   ", &players()), PlayerLookup::NotFound);
+        assert_eq!(match_player_name("tommy", &[]), PlayerLookup::NotFound);
+        assert_eq!(PlayerLookup::NotFound.found(), None);
+    }
+}
@@ -0,0 +1,755 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+
+use crate::func::{ObjectMethods, PickupMethods, VehicleMethods};
+use crate::utils::{Quaternionf32, Vectorf32};
+use crate::{ObjectId, VehicleId, vcmp_func};
+
+/// 物体的朝向
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ObjectRotation {
+    #[default]
+    None,
+    Quaternion(Quaternionf32),
+    /// 弧度
+    Euler(Vectorf32),
+}
+
+impl ObjectRotation {
+    /// 轴角 (弧度) 转成四元数
+    pub fn from_axis_angle(axis: Vectorf32, angle: f32) -> Self {
+        let length = axis.length();
+        if length == 0.0 {
+            return Self::None;
+        }
+        let (sin, cos) = (angle / 2.0).sin_cos();
+        Self::Quaternion(Quaternionf32::new(
+            axis.x / length * sin,
+            axis.y / length * sin,
+            axis.z / length * sin,
+            cos,
+        ))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectPlacement {
+    pub model: i32,
+    pub position: Vectorf32,
+    pub rotation: ObjectRotation,
+    pub alpha: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VehiclePlacement {
+    pub model: i32,
+    pub position: Vectorf32,
+    pub angle: f32,
+    pub primary_colour: i32,
+    pub secondary_colour: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PickupPlacement {
+    pub model: i32,
+    pub position: Vectorf32,
+    pub quantity: i32,
+    pub alpha: i32,
+    pub is_automatic: bool,
+}
+
+/// 解析好的地图文件
+///
+/// 支持两种格式:
+/// - VC:MP 地图编辑器导出的 xml (`<item model=".."><position x=".." .. /><rotation format="axisangle" .. /></item>`),
+///   另外也认 `<vehicle>` 和 `<pickup>`, 属性可以直接写在元素上
+/// - ini / csv 的放置列表, 一行一个, 用 `[objects]` `[vehicles]` `[pickups]` 分段,
+///   或者第一列写 `object` / `vehicle` / `pickup`
+///   - object: `model, x, y, z[, rx, ry, rz][, alpha]` (欧拉角, 弧度)
+///   - vehicle: `model, x, y, z[, angle][, colour1, colour2]`
+///   - pickup: `model, x, y, z[, quantity][, alpha][, automatic]`
+#[derive(Debug, Clone, Default)]
+pub struct MapFile {
+    pub objects: Vec<ObjectPlacement>,
+    pub vehicles: Vec<VehiclePlacement>,
+    pub pickups: Vec<PickupPlacement>,
+}
+
+impl MapFile {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty() && self.vehicles.is_empty() && self.pickups.is_empty()
+    }
+
+    /// 按扩展名选择格式, `.xml` 走 xml, 其他的当放置列表
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)?;
+        let is_xml = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("xml"));
+        if is_xml {
+            Self::parse_xml(&content)
+        } else {
+            Self::parse_list(&content)
+        }
+    }
+
+    /// 把另一个地图的内容合并进来
+    pub fn extend(&mut self, other: MapFile) {
+        self.objects.extend(other.objects);
+        self.vehicles.extend(other.vehicles);
+        self.pickups.extend(other.pickups);
+    }
+
+    /// 不认识的元素当成容器, 继续找它下面的元素,
+    /// 所以 `<map><objects><item .. /></objects></map>` 这种嵌套也能读
+    pub fn parse_xml(content: &str) -> io::Result<Self> {
+        let mut map = Self::new();
+        for element in parse_xml_elements(content)? {
+            map.push_xml_element(&element)?;
+        }
+        Ok(map)
+    }
+
+    pub fn parse_list(content: &str) -> io::Result<Self> {
+        let mut map = Self::new();
+        let mut section: Option<String> = None;
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+            let invalid = |reason: &str| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {}: {reason}: {line}", index + 1),
+                )
+            };
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = Some(name.trim().to_ascii_lowercase());
+                continue;
+            }
+            let mut fields: Vec<&str> = line
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|field| !field.is_empty())
+                .collect();
+            let kind = match fields.first() {
+                Some(first) if first.parse::<f32>().is_err() => {
+                    let kind = first.to_ascii_lowercase();
+                    fields.remove(0);
+                    kind
+                }
+                _ => section
+                    .clone()
+                    .ok_or_else(|| invalid("unknown placement kind"))?,
+            };
+            let numbers = fields
+                .iter()
+                .map(|field| field.parse::<f32>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| invalid("invalid number"))?;
+            if numbers.len() < 4 {
+                return Err(invalid("expected at least `model, x, y, z`"));
+            }
+            let model = numbers[0] as i32;
+            let position = Vectorf32::new(numbers[1], numbers[2], numbers[3]);
+            let rest = &numbers[4..];
+            match kind.trim_end_matches('s') {
+                "object" | "item" => map.objects.push(ObjectPlacement {
+                    model,
+                    position,
+                    rotation: if rest.len() >= 3 {
+                        ObjectRotation::Euler(Vectorf32::new(rest[0], rest[1], rest[2]))
+                    } else {
+                        ObjectRotation::None
+                    },
+                    alpha: rest.get(3).map(|a| *a as i32).unwrap_or(255),
+                }),
+                "vehicle" => map.vehicles.push(VehiclePlacement {
+                    model,
+                    position,
+                    angle: rest.first().copied().unwrap_or(0.0),
+                    primary_colour: rest.get(1).map(|c| *c as i32).unwrap_or(-1),
+                    secondary_colour: rest.get(2).map(|c| *c as i32).unwrap_or(-1),
+                }),
+                "pickup" => map.pickups.push(PickupPlacement {
+                    model,
+                    position,
+                    quantity: rest.first().map(|q| *q as i32).unwrap_or(1),
+                    alpha: rest.get(1).map(|a| *a as i32).unwrap_or(255),
+                    is_automatic: rest.get(2).is_none_or(|a| *a != 0.0),
+                }),
+                _ => return Err(invalid("unknown placement kind")),
+            }
+        }
+        Ok(map)
+    }
+
+    fn push_xml_element(&mut self, element: &XmlElement) -> io::Result<()> {
+        match element.name.to_ascii_lowercase().as_str() {
+            "item" | "object" => self.objects.push(ObjectPlacement {
+                model: element.attr_i32("model")?,
+                position: element.position()?,
+                rotation: element.rotation()?,
+                alpha: element.attr_i32_or("alpha", 255)?,
+            }),
+            "vehicle" => self.vehicles.push(VehiclePlacement {
+                model: element.attr_i32("model")?,
+                position: element.position()?,
+                angle: element.attr_f32_or("angle", 0.0)?,
+                primary_colour: element.attr_i32_or("colour1", -1)?,
+                secondary_colour: element.attr_i32_or("colour2", -1)?,
+            }),
+            "pickup" => self.pickups.push(PickupPlacement {
+                model: element.attr_i32("model")?,
+                position: element.position()?,
+                quantity: element.attr_i32_or("quantity", 1)?,
+                alpha: element.attr_i32_or("alpha", 255)?,
+                is_automatic: element.attr_i32_or("automatic", 1)? != 0,
+            }),
+            // 容器 (<map> <objects> <group> ...), 继续往下找
+            _ => {
+                for child in &element.children {
+                    self.push_xml_element(child)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 把所有东西刷到 `world` 里
+    ///
+    /// 创建失败的会跳过, 已经创建的都记在返回的 LoadedMap 里
+    pub fn spawn(&self, world: i32) -> LoadedMap {
+        let func = vcmp_func();
+        let mut loaded = LoadedMap {
+            world,
+            ..Default::default()
+        };
+        for object in &self.objects {
+            let id = func.create_object(object.model, world, object.position, object.alpha);
+            if id < 0 {
+                continue;
+            }
+            let _ = match object.rotation {
+                ObjectRotation::None => Ok(()),
+                ObjectRotation::Quaternion(rotation) => func.rotate_object_to(id, rotation, 0),
+                ObjectRotation::Euler(rotation) => func.rotate_object_to_euler(id, rotation, 0),
+            };
+            loaded.objects.push(id);
+        }
+        for vehicle in &self.vehicles {
+            let id = func.create_vehicle(
+                vehicle.model,
+                world,
+                vehicle.position,
+                vehicle.angle,
+                vehicle.primary_colour,
+                vehicle.secondary_colour,
+            );
+            if id >= 0 {
+                loaded.vehicles.push(id);
+            }
+        }
+        for pickup in &self.pickups {
+            let id = func.create_pickup(
+                pickup.model,
+                world,
+                pickup.quantity,
+                pickup.position,
+                pickup.alpha,
+                pickup.is_automatic,
+            );
+            if id >= 0 {
+                loaded.pickups.push(id);
+            }
+        }
+        loaded
+    }
+}
+
+/// `MapFile::spawn` 刷出来的东西
+///
+/// 调用 `unload` 删除, drop 的时候不会自动删
+#[derive(Debug, Clone, Default)]
+pub struct LoadedMap {
+    world: i32,
+    objects: Vec<ObjectId>,
+    vehicles: Vec<VehicleId>,
+    pickups: Vec<i32>,
+}
+
+impl LoadedMap {
+    pub fn world(&self) -> i32 {
+        self.world
+    }
+
+    pub fn objects(&self) -> &[ObjectId] {
+        &self.objects
+    }
+
+    pub fn vehicles(&self) -> &[VehicleId] {
+        &self.vehicles
+    }
+
+    pub fn pickups(&self) -> &[i32] {
+        &self.pickups
+    }
+
+    pub fn len(&self) -> usize {
+        self.objects.len() + self.vehicles.len() + self.pickups.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 删除所有刷出来的东西
+    pub fn unload(self) {
+        let func = vcmp_func();
+        for object in self.objects {
+            let _ = func.delete_object(object);
+        }
+        for vehicle in self.vehicles {
+            let _ = func.delete_vehicle(vehicle);
+        }
+        for pickup in self.pickups {
+            let _ = func.delete_pickup(pickup);
+        }
+    }
+}
+
+/*
+    一个只够读地图文件的 xml 解析
+*/
+
+#[derive(Debug, Default)]
+struct XmlElement {
+    name: String,
+    attrs: HashMap<String, String>,
+    children: Vec<XmlElement>,
+}
+
+impl XmlElement {
+    fn invalid(&self, reason: &str) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("<{}>: {reason}", self.name),
+        )
+    }
+
+    fn child(&self, name: &str) -> Option<&XmlElement> {
+        self.children
+            .iter()
+            .find(|child| child.name.eq_ignore_ascii_case(name))
+    }
+
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn attr_f32(&self, name: &str) -> io::Result<f32> {
+        self.attr(name)
+            .ok_or_else(|| self.invalid(&format!("missing attribute `{name}`")))?
+            .trim()
+            .parse()
+            .map_err(|_| self.invalid(&format!("invalid attribute `{name}`")))
+    }
+
+    fn attr_f32_or(&self, name: &str, default: f32) -> io::Result<f32> {
+        match self.attr(name) {
+            Some(_) => self.attr_f32(name),
+            None => Ok(default),
+        }
+    }
+
+    fn attr_i32(&self, name: &str) -> io::Result<i32> {
+        Ok(self.attr_f32(name)? as i32)
+    }
+
+    fn attr_i32_or(&self, name: &str, default: i32) -> io::Result<i32> {
+        Ok(self.attr_f32_or(name, default as f32)? as i32)
+    }
+
+    fn vector(&self) -> io::Result<Vectorf32> {
+        Ok(Vectorf32::new(
+            self.attr_f32("x")?,
+            self.attr_f32("y")?,
+            self.attr_f32("z")?,
+        ))
+    }
+
+    /// `<position x y z />` 子元素, 或者直接写在自己身上
+    fn position(&self) -> io::Result<Vectorf32> {
+        match self.child("position") {
+            Some(position) => position.vector(),
+            None => self.vector(),
+        }
+    }
+
+    fn rotation(&self) -> io::Result<ObjectRotation> {
+        let Some(rotation) = self.child("rotation") else {
+            return Ok(ObjectRotation::None);
+        };
+        let format = rotation.attr("format").unwrap_or("axisangle");
+        Ok(match format.to_ascii_lowercase().as_str() {
+            "axisangle" => {
+                ObjectRotation::from_axis_angle(rotation.vector()?, rotation.attr_f32("angle")?)
+            }
+            "quaternion" => ObjectRotation::Quaternion(Quaternionf32::new(
+                rotation.attr_f32("x")?,
+                rotation.attr_f32("y")?,
+                rotation.attr_f32("z")?,
+                rotation.attr_f32("w")?,
+            )),
+            "euler" => ObjectRotation::Euler(rotation.vector()?),
+            _ => return Err(rotation.invalid("unknown rotation format")),
+        })
+    }
+}
+
+/// 返回所有顶层元素, 不关心文本内容
+fn parse_xml_elements(content: &str) -> io::Result<Vec<XmlElement>> {
+    let invalid = |reason: &str| io::Error::new(io::ErrorKind::InvalidData, reason.to_string());
+    let mut stack: Vec<XmlElement> = vec![XmlElement::default()];
+    let mut rest = content;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start..];
+        if let Some(after) = rest.strip_prefix("<!--") {
+            let end = after
+                .find("-->")
+                .ok_or_else(|| invalid("unclosed comment"))?;
+            rest = &after[end + 3..];
+            continue;
+        }
+        let end = find_tag_end(rest).ok_or_else(|| invalid("unclosed tag"))?;
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+        if tag.starts_with('?') || tag.starts_with('!') {
+            continue;
+        }
+        if let Some(name) = tag.strip_prefix('/') {
+            let element = stack
+                .pop()
+                .ok_or_else(|| invalid("unexpected closing tag"))?;
+            if !element.name.eq_ignore_ascii_case(name.trim()) || stack.is_empty() {
+                return Err(invalid(&format!(
+                    "mismatched closing tag </{}>",
+                    name.trim()
+                )));
+            }
+            stack.last_mut().unwrap().children.push(element);
+            continue;
+        }
+        let self_closing = tag.ends_with('/');
+        let element = parse_xml_tag(tag.trim_end_matches('/'))?;
+        if self_closing {
+            stack.last_mut().unwrap().children.push(element);
+        } else {
+            stack.push(element);
+        }
+    }
+    if stack.len() != 1 {
+        return Err(invalid("unclosed element"));
+    }
+    Ok(stack.pop().unwrap().children)
+}
+
+/// 标签结束的 `>` 的位置, 跳过引号里的内容 (属性值里可以有 `>`)
+fn find_tag_end(tag: &str) -> Option<usize> {
+    let mut quote = None;
+    for (index, c) in tag.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '>') => return Some(index),
+            (Some(open), c) if c == open => quote = None,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn parse_xml_tag(tag: &str) -> io::Result<XmlElement> {
+    let tag = tag.trim();
+    let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+    let mut element = XmlElement {
+        name: tag[..name_end].to_string(),
+        ..Default::default()
+    };
+    let mut rest = tag[name_end..].trim_start();
+    while !rest.is_empty() {
+        let eq = rest.find('=').ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("<{}>: invalid attribute", element.name),
+            )
+        })?;
+        let key = rest[..eq].trim().to_string();
+        let value_part = rest[eq + 1..].trim_start();
+        let quote = value_part
+            .chars()
+            .next()
+            .filter(|c| *c == '"' || *c == '\'');
+        let Some(quote) = quote else {
+            return Err(element.invalid(&format!("unquoted attribute `{key}`")));
+        };
+        let value_end = value_part[1..]
+            .find(quote)
+            .ok_or_else(|| element.invalid(&format!("unclosed attribute `{key}`")))?;
+        let value = &value_part[1..1 + value_end];
+        element.attrs.insert(key, unescape_xml(value));
+        rest = value_part[value_end + 2..].trim_start();
+    }
+    Ok(element)
+}
+
+fn unescape_xml(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_message(result: io::Result<MapFile>) -> String {
+        let error = result.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        error.to_string()
+    }
+
+    #[test]
+    fn xml_rotation_forms() {
+        let map = MapFile::parse_xml(
+            r#"<map>
+                <item model="100">
+                    <position x="1" y="2" z="3" />
+                    <rotation format="axisangle" x="0" y="0" z="2" angle="3.1415927" />
+                </item>
+                <item model="101" alpha="128">
+                    <position x="0" y="0" z="0" />
+                    <rotation format="quaternion" x="0.1" y="0.2" z="0.3" w="0.9" />
+                </item>
+                <item model="102" x="4" y="5" z="6">
+                    <rotation format="euler" x="0.5" y="0" z="1.5" />
+                </item>
+                <object model="103" x="0" y="0" z="0" />
+            </map>"#,
+        )
+        .unwrap();
+        assert_eq!(map.objects.len(), 4);
+
+        let first = &map.objects[0];
+        assert_eq!(first.model, 100);
+        assert_eq!(first.position, Vectorf32::new(1.0, 2.0, 3.0));
+        assert_eq!(first.alpha, 255);
+        let ObjectRotation::Quaternion(rotation) = first.rotation else {
+            panic!("expected quaternion, got {:?}", first.rotation);
+        };
+        assert!(rotation.x.abs() < 1e-6 && rotation.y.abs() < 1e-6);
+        assert!((rotation.z - 1.0).abs() < 1e-6 && rotation.w.abs() < 1e-6);
+
+        assert_eq!(
+            map.objects[1].rotation,
+            ObjectRotation::Quaternion(Quaternionf32::new(0.1, 0.2, 0.3, 0.9))
+        );
+        assert_eq!(map.objects[1].alpha, 128);
+        assert_eq!(map.objects[2].position, Vectorf32::new(4.0, 5.0, 6.0));
+        assert_eq!(
+            map.objects[2].rotation,
+            ObjectRotation::Euler(Vectorf32::new(0.5, 0.0, 1.5))
+        );
+        assert_eq!(map.objects[3].rotation, ObjectRotation::None);
+    }
+
+    #[test]
+    fn xml_vehicles_and_pickups() {
+        let map = MapFile::parse_xml(
+            r#"<?xml version="1.0"?>
+            <!-- 注释 -->
+            <vehicle model="130" x="1" y="2" z="3" angle="1.5" colour1="4" colour2="5" />
+            <pickup model="366" x="7" y="8" z="9" quantity="2" automatic="0" />"#,
+        )
+        .unwrap();
+        assert_eq!(
+            map.vehicles,
+            vec![VehiclePlacement {
+                model: 130,
+                position: Vectorf32::new(1.0, 2.0, 3.0),
+                angle: 1.5,
+                primary_colour: 4,
+                secondary_colour: 5,
+            }]
+        );
+        assert_eq!(
+            map.pickups,
+            vec![PickupPlacement {
+                model: 366,
+                position: Vectorf32::new(7.0, 8.0, 9.0),
+                quantity: 2,
+                alpha: 255,
+                is_automatic: false,
+            }]
+        );
+    }
+
+    #[test]
+    fn xml_quoted_angle_bracket() {
+        let map = MapFile::parse_xml(
+            r#"<map name="a > b" note='<x>'>
+                <item model="1" x="0" y="0" z="0" comment="-> here" />
+                <item model="2" x="0" y="0" z="0" />
+            </map>"#,
+        )
+        .unwrap();
+        let models: Vec<i32> = map.objects.iter().map(|object| object.model).collect();
+        assert_eq!(models, vec![1, 2]);
+        assert_eq!(
+            error_message(MapFile::parse_xml(r#"<item model="1>"#)),
+            "unclosed tag"
+        );
+    }
+
+    #[test]
+    fn xml_nested_containers() {
+        let map = MapFile::parse_xml(
+            r#"<map>
+                <objects>
+                    <group name="a">
+                        <item model="1" x="0" y="0" z="0" />
+                    </group>
+                    <item model="2" x="0" y="0" z="0" />
+                </objects>
+                <vehicles><vehicle model="130" x="0" y="0" z="0" /></vehicles>
+                <description />
+            </map>
+            <pickups><pickup model="366" x="0" y="0" z="0" /></pickups>"#,
+        )
+        .unwrap();
+        let models: Vec<i32> = map.objects.iter().map(|object| object.model).collect();
+        assert_eq!(models, vec![1, 2]);
+        assert_eq!(map.vehicles.len(), 1);
+        assert_eq!(map.pickups.len(), 1);
+    }
+
+    #[test]
+    fn xml_malformed() {
+        assert_eq!(
+            error_message(MapFile::parse_xml(
+                r#"<map><item model="1" x="0" y="0" z="0" />"#
+            )),
+            "unclosed element"
+        );
+        assert_eq!(
+            error_message(MapFile::parse_xml("<map><item></map></item>")),
+            "mismatched closing tag </map>"
+        );
+        assert_eq!(
+            error_message(MapFile::parse_xml("<map><item model=\"1\"")),
+            "unclosed tag"
+        );
+        assert!(
+            error_message(MapFile::parse_xml(r#"<item model="1" x="0" y="0" />"#))
+                .contains("missing attribute `z`")
+        );
+        assert!(
+            error_message(MapFile::parse_xml(
+                r#"<item model="abc" x="0" y="0" z="0" />"#
+            ))
+            .contains("invalid attribute `model`")
+        );
+        let unknown_format = MapFile::parse_xml(
+            r#"<item model="1" x="0" y="0" z="0"><rotation format="matrix" /></item>"#,
+        );
+        assert!(error_message(unknown_format).contains("unknown rotation format"));
+        assert!(
+            error_message(MapFile::parse_xml(r#"<item model=1 x="0" y="0" z="0" />"#))
+                .contains("unquoted attribute `model`")
+        );
+    }
+
+    #[test]
+    fn list_sections_and_kind_column() {
+        let map = MapFile::parse_list(
+            "# 注释\n\
+             [objects]\n\
+             100, 1, 2, 3\n\
+             101, 1, 2, 3, 0.5, 0, 1.5, 128\n\
+             \n\
+             [vehicles]\n\
+             130 1 2 3 1.5 4 5\n\
+             ; 注释\n\
+             pickup, 366, 7, 8, 9, 2, 255, 0\n\
+             object, 102, 0, 0, 0\n",
+        )
+        .unwrap();
+        assert_eq!(
+            map.objects,
+            vec![
+                ObjectPlacement {
+                    model: 100,
+                    position: Vectorf32::new(1.0, 2.0, 3.0),
+                    rotation: ObjectRotation::None,
+                    alpha: 255,
+                },
+                ObjectPlacement {
+                    model: 101,
+                    position: Vectorf32::new(1.0, 2.0, 3.0),
+                    rotation: ObjectRotation::Euler(Vectorf32::new(0.5, 0.0, 1.5)),
+                    alpha: 128,
+                },
+                ObjectPlacement {
+                    model: 102,
+                    position: Vectorf32::new(0.0, 0.0, 0.0),
+                    rotation: ObjectRotation::None,
+                    alpha: 255,
+                },
+            ]
+        );
+        assert_eq!(
+            map.vehicles,
+            vec![VehiclePlacement {
+                model: 130,
+                position: Vectorf32::new(1.0, 2.0, 3.0),
+                angle: 1.5,
+                primary_colour: 4,
+                secondary_colour: 5,
+            }]
+        );
+        assert_eq!(map.pickups.len(), 1);
+        assert_eq!(map.pickups[0].quantity, 2);
+        assert!(!map.pickups[0].is_automatic);
+    }
+
+    #[test]
+    fn list_malformed() {
+        assert_eq!(
+            error_message(MapFile::parse_list("100, 1, 2, 3")),
+            "line 1: unknown placement kind: 100, 1, 2, 3"
+        );
+        assert_eq!(
+            error_message(MapFile::parse_list("[objects]\n100, 1, two, 3")),
+            "line 2: invalid number: 100, 1, two, 3"
+        );
+        assert_eq!(
+            error_message(MapFile::parse_list("[vehicles]\n130, 1, 2")),
+            "line 2: expected at least `model, x, y, z`: 130, 1, 2"
+        );
+        assert_eq!(
+            error_message(MapFile::parse_list("building, 1, 2, 3, 4")),
+            "line 1: unknown placement kind: building, 1, 2, 3, 4"
+        );
+    }
+}
@@ -0,0 +1,386 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+use std::io;
+use std::path::Path;
+
+use crate::func::MiscMethods;
+use crate::func::misc::map_object_tenths;
+use crate::utils::Vectorf32;
+use crate::vcmp_func;
+
+/// 一个被隐藏的地图物体
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RemovedMapObject {
+    pub model: i32,
+    pub position: Vectorf32,
+}
+
+impl RemovedMapObject {
+    pub fn new(model: i32, position: Vectorf32) -> Self {
+        Self { model, position }
+    }
+
+    /// 服务器实际比较用的 key (坐标精度只有 0.1)
+    fn key(&self) -> (i32, (i16, i16, i16)) {
+        (self.model, map_object_tenths(self.position))
+    }
+}
+
+impl Display for RemovedMapObject {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}",
+            self.model, self.position.x, self.position.y, self.position.z
+        )
+    }
+}
+
+/// 一组要隐藏的地图物体, 一般一个地图包一组
+///
+/// 文件格式是一行一个 `model x y z`, `#` 开头的是注释
+#[derive(Debug, Clone, Default)]
+pub struct MapObjectRemoval {
+    name: String,
+    objects: Vec<RemovedMapObject>,
+}
+
+impl MapObjectRemoval {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            objects: Vec::new(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn objects(&self) -> &[RemovedMapObject] {
+        &self.objects
+    }
+
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    /// 重复的物体会被忽略, 返回是否真的加进去了
+    pub fn add(&mut self, model: i32, position: Vectorf32) -> bool {
+        let object = RemovedMapObject::new(model, position);
+        if self.contains(model, position) {
+            return false;
+        }
+        self.objects.push(object);
+        true
+    }
+
+    pub fn remove(&mut self, model: i32, position: Vectorf32) -> bool {
+        let key = RemovedMapObject::new(model, position).key();
+        let before = self.objects.len();
+        self.objects.retain(|object| object.key() != key);
+        self.objects.len() != before
+    }
+
+    pub fn contains(&self, model: i32, position: Vectorf32) -> bool {
+        let key = RemovedMapObject::new(model, position).key();
+        self.objects.iter().any(|object| object.key() == key)
+    }
+
+    /// 某个模型被隐藏的所有位置
+    pub fn find_model(&self, model: i32) -> Vec<RemovedMapObject> {
+        self.objects
+            .iter()
+            .filter(|object| object.model == model)
+            .copied()
+            .collect()
+    }
+
+    pub fn parse(name: &str, content: &str) -> io::Result<Self> {
+        let mut removal = Self::new(name);
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = |reason: &str| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {}: {reason}: {line}", index + 1),
+                )
+            };
+            let parts: Vec<&str> = line
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|part| !part.is_empty())
+                .collect();
+            if parts.len() != 4 {
+                return Err(invalid("expected `model x y z`"));
+            }
+            let model = parts[0]
+                .parse::<i32>()
+                .map_err(|_| invalid("invalid model"))?;
+            let mut coords = [0f32; 3];
+            for (coord, part) in coords.iter_mut().zip(&parts[1..]) {
+                *coord = part.parse().map_err(|_| invalid("invalid coordinate"))?;
+            }
+            removal.add(model, Vectorf32::from((coords[0], coords[1], coords[2])));
+        }
+        Ok(removal)
+    }
+
+    /// 从文件读取, 名字用文件名 (不带扩展名)
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        Self::parse(&name, &std::fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, self.to_string())
+    }
+}
+
+impl Display for MapObjectRemoval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "# {}", self.name)?;
+        for object in &self.objects {
+            writeln!(f, "{object}")?;
+        }
+        Ok(())
+    }
+}
+
+/// 管理多组 MapObjectRemoval
+///
+/// 同一个物体可能被好几组隐藏, 这里按引用计数来,
+/// 只有最后一组撤销的时候才会真的 show 回来
+#[derive(Debug, Default)]
+pub struct MapObjectRemovals {
+    sets: HashMap<String, MapObjectRemoval>,
+    active: HashSet<String>,
+    hidden: HashMap<(i32, (i16, i16, i16)), usize>,
+}
+
+impl MapObjectRemovals {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 注册一组, 同名的旧组会先撤销再替换掉
+    pub fn insert(&mut self, removal: MapObjectRemoval) {
+        let name = removal.name().to_string();
+        let was_active = self.revert(&name);
+        self.sets.insert(name.clone(), removal);
+        if was_active {
+            self.apply(&name);
+        }
+    }
+
+    /// 撤销并移除一组
+    pub fn remove(&mut self, name: &str) -> Option<MapObjectRemoval> {
+        self.revert(name);
+        self.sets.remove(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&MapObjectRemoval> {
+        self.sets.get(name)
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.sets.keys().map(|name| name.as_str()).collect()
+    }
+
+    /// 隐藏这一组里的物体, 已经生效或者不存在的返回 false
+    pub fn apply(&mut self, name: &str) -> bool {
+        let Some(to_hide) = self.acquire(name) else {
+            return false;
+        };
+        let func = vcmp_func();
+        for object in to_hide {
+            func.hide_map_object(object.model, object.position);
+        }
+        true
+    }
+
+    /// 撤销这一组, 没有别的组隐藏的物体会被 show 回来
+    pub fn revert(&mut self, name: &str) -> bool {
+        let Some(to_show) = self.release(name) else {
+            return false;
+        };
+        let func = vcmp_func();
+        for object in to_show {
+            func.show_map_object(object.model, object.position);
+        }
+        true
+    }
+
+    /// 记下这一组生效, 返回之前没被隐藏、现在要 hide 的物体
+    fn acquire(&mut self, name: &str) -> Option<Vec<RemovedMapObject>> {
+        let removal = self.sets.get(name)?;
+        if !self.active.insert(name.to_string()) {
+            return None;
+        }
+        let mut to_hide = Vec::new();
+        for object in removal.objects() {
+            let count = self.hidden.entry(object.key()).or_insert(0);
+            if *count == 0 {
+                to_hide.push(*object);
+            }
+            *count += 1;
+        }
+        Some(to_hide)
+    }
+
+    /// 记下这一组撤销, 返回没有别的组隐藏、现在要 show 的物体
+    fn release(&mut self, name: &str) -> Option<Vec<RemovedMapObject>> {
+        if !self.active.remove(name) {
+            return None;
+        }
+        let removal = self.sets.get(name)?;
+        let mut to_show = Vec::new();
+        for object in removal.objects() {
+            let key = object.key();
+            if let Some(count) = self.hidden.get_mut(&key) {
+                *count -= 1;
+                if *count == 0 {
+                    self.hidden.remove(&key);
+                    to_show.push(*object);
+                }
+            }
+        }
+        Some(to_show)
+    }
+
+    pub fn revert_all(&mut self) {
+        let names: Vec<String> = self.active.iter().cloned().collect();
+        for name in names {
+            self.revert(&name);
+        }
+    }
+
+    pub fn is_active(&self, name: &str) -> bool {
+        self.active.contains(name)
+    }
+
+    pub fn active_names(&self) -> Vec<&str> {
+        self.active.iter().map(|name| name.as_str()).collect()
+    }
+
+    /// 这个物体现在是否被某一组隐藏着
+    pub fn is_hidden(&self, model: i32, position: Vectorf32) -> bool {
+        self.hidden
+            .contains_key(&RemovedMapObject::new(model, position).key())
+    }
+
+    /// 隐藏了这个物体的组
+    pub fn hidden_by(&self, model: i32, position: Vectorf32) -> Vec<&str> {
+        self.active
+            .iter()
+            .filter(|name| {
+                self.sets
+                    .get(name.as_str())
+                    .is_some_and(|removal| removal.contains(model, position))
+            })
+            .map(|name| name.as_str())
+            .collect()
+    }
+
+    /// 当前隐藏着的物体数量
+    pub fn hidden_count(&self) -> usize {
+        self.hidden.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn removal(name: &str, objects: &[(i32, f32)]) -> MapObjectRemoval {
+        let mut removal = MapObjectRemoval::new(name);
+        for &(model, x) in objects {
+            removal.add(model, Vectorf32::new(x, 2.0, 3.0));
+        }
+        removal
+    }
+
+    fn models(objects: Option<Vec<RemovedMapObject>>) -> Vec<i32> {
+        objects.unwrap().iter().map(|object| object.model).collect()
+    }
+
+    #[test]
+    fn duplicates_use_server_precision() {
+        let mut removal = MapObjectRemoval::new("docks");
+        assert!(removal.add(100, Vectorf32::new(1.23, 2.0, 3.0)));
+        // 服务器只看到 0.1, 1.26 和 1.23 是同一个位置
+        assert!(!removal.add(100, Vectorf32::new(1.26, 2.0, 3.0)));
+        assert!(removal.add(100, Vectorf32::new(1.33, 2.0, 3.0)));
+        assert!(removal.add(101, Vectorf32::new(1.23, 2.0, 3.0)));
+        assert_eq!(removal.len(), 3);
+        assert_eq!(removal.find_model(100).len(), 2);
+        assert!(removal.remove(100, Vectorf32::new(1.29, 2.0, 3.0)));
+        assert!(!removal.contains(100, Vectorf32::new(1.23, 2.0, 3.0)));
+    }
+
+    #[test]
+    fn file_round_trip() {
+        let mut original = MapObjectRemoval::new("docks");
+        original.add(100, Vectorf32::new(-1.5, 2.25, 300.0));
+        original.add(2045, Vectorf32::new(0.1, -0.2, 0.3));
+        let parsed = MapObjectRemoval::parse("docks", &original.to_string()).unwrap();
+        assert_eq!(parsed.objects(), original.objects());
+
+        let parsed = MapObjectRemoval::parse("x", "# 注释\n\n100, 1, 2, 3\n101\t4 5 6\n").unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed.objects()[1].position, Vectorf32::new(4.0, 5.0, 6.0));
+    }
+
+    #[test]
+    fn parse_errors() {
+        let message = |content: &str| {
+            let error = MapObjectRemoval::parse("x", content).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+            error.to_string()
+        };
+        assert_eq!(
+            message("100 1 2"),
+            "line 1: expected `model x y z`: 100 1 2"
+        );
+        assert_eq!(message("\n1.5 1 2 3"), "line 2: invalid model: 1.5 1 2 3");
+        assert_eq!(
+            message("100 1 y 3"),
+            "line 1: invalid coordinate: 100 1 y 3"
+        );
+    }
+
+    #[test]
+    fn shared_objects_are_ref_counted() {
+        let mut removals = MapObjectRemovals::new();
+        removals.insert(removal("a", &[(1, 0.0), (2, 0.0)]));
+        removals.insert(removal("b", &[(2, 0.0), (3, 0.0)]));
+
+        assert_eq!(models(removals.acquire("a")), vec![1, 2]);
+        // 2 已经被 a 隐藏了
+        assert_eq!(models(removals.acquire("b")), vec![3]);
+        assert!(removals.acquire("b").is_none());
+        assert!(removals.acquire("missing").is_none());
+        assert_eq!(removals.hidden_count(), 3);
+        let mut hidden_by = removals.hidden_by(2, Vectorf32::new(0.0, 2.0, 3.0));
+        hidden_by.sort();
+        assert_eq!(hidden_by, vec!["a", "b"]);
+
+        // 2 还被 b 隐藏着, 不会 show 回来
+        assert_eq!(models(removals.release("a")), vec![1]);
+        assert!(removals.is_hidden(2, Vectorf32::new(0.0, 2.0, 3.0)));
+        assert!(removals.release("a").is_none());
+        assert_eq!(models(removals.release("b")), vec![2, 3]);
+        assert_eq!(removals.hidden_count(), 0);
+        assert!(removals.active_names().is_empty());
+    }
+}
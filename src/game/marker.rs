@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+
+use crate::catalog::BlipSprite;
+use crate::func::MarkerMethods;
+use crate::utils::{Color, Vectorf32};
+use crate::{MarkerId, VcmpError, VcmpResult, vcmp_func};
+
+/// 创建小地图图标用的参数
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlipDefinition {
+    pub world: i32,
+    pub position: Vectorf32,
+    pub scale: i32,
+    pub color: Color,
+    pub sprite: BlipSprite,
+    /// 指定图标的槽位, None 时由服务器分配
+    ///
+    /// 服务器的图标是按世界显示的, 这个槽位没法让图标只给某个玩家看
+    pub index: Option<i32>,
+}
+
+impl BlipDefinition {
+    pub fn new(world: i32, position: Vectorf32, sprite: BlipSprite) -> Self {
+        Self {
+            world,
+            position,
+            scale: 1,
+            color: Color::from_rgb(0xFFFFFF, None),
+            sprite,
+            index: None,
+        }
+    }
+
+    pub fn scale(mut self, scale: i32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn index(mut self, index: i32) -> Self {
+        self.index = Some(index);
+        self
+    }
+
+    fn create(&self) -> VcmpResult<MarkerId> {
+        let marker = vcmp_func().create_marker(
+            self.world,
+            self.position,
+            self.scale,
+            self.color,
+            self.sprite.into(),
+            self.index,
+        );
+        if marker < 0 {
+            Err(VcmpError::PoolExhausted)
+        } else {
+            Ok(marker)
+        }
+    }
+}
+
+/// `MarkerManager` 里一个图标的句柄, 重新创建之后也不会变
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BlipHandle(u32);
+
+#[derive(Debug, Clone)]
+struct ManagedBlip {
+    definition: BlipDefinition,
+    group: Option<String>,
+    /// 隐藏时为 None
+    marker: Option<MarkerId>,
+}
+
+/// 管理小地图图标
+///
+/// 每个图标记住自己的参数, 所以可以整组隐藏 / 显示, 换世界或者换位置时会自动删掉重建
+#[derive(Debug, Clone, Default)]
+pub struct MarkerManager {
+    next_handle: u32,
+    blips: HashMap<BlipHandle, ManagedBlip>,
+}
+
+impl MarkerManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 创建并显示一个图标
+    pub fn create(
+        &mut self,
+        definition: BlipDefinition,
+        group: Option<&str>,
+    ) -> VcmpResult<BlipHandle> {
+        let marker = definition.create()?;
+        let handle = BlipHandle(self.next_handle);
+        self.next_handle += 1;
+        self.blips.insert(
+            handle,
+            ManagedBlip {
+                definition,
+                group: group.map(|group| group.to_string()),
+                marker: Some(marker),
+            },
+        );
+        Ok(handle)
+    }
+
+    pub fn remove(&mut self, handle: BlipHandle) -> bool {
+        let Some(blip) = self.blips.remove(&handle) else {
+            return false;
+        };
+        if let Some(marker) = blip.marker {
+            let _ = vcmp_func().destroy_marker(marker);
+        }
+        true
+    }
+
+    pub fn clear(&mut self) {
+        let handles: Vec<BlipHandle> = self.blips.keys().copied().collect();
+        for handle in handles {
+            self.remove(handle);
+        }
+    }
+
+    pub fn definition(&self, handle: BlipHandle) -> Option<&BlipDefinition> {
+        self.blips.get(&handle).map(|blip| &blip.definition)
+    }
+
+    /// 当前的服务器 marker id, 隐藏时为 None
+    pub fn marker_id(&self, handle: BlipHandle) -> Option<MarkerId> {
+        self.blips.get(&handle)?.marker
+    }
+
+    pub fn is_visible(&self, handle: BlipHandle) -> bool {
+        self.marker_id(handle).is_some()
+    }
+
+    pub fn group_of(&self, handle: BlipHandle) -> Option<&str> {
+        self.blips.get(&handle)?.group.as_deref()
+    }
+
+    pub fn group(&self, group: &str) -> Vec<BlipHandle> {
+        let mut handles: Vec<BlipHandle> = self
+            .blips
+            .iter()
+            .filter(|(_, blip)| blip.group.as_deref() == Some(group))
+            .map(|(&handle, _)| handle)
+            .collect();
+        handles.sort();
+        handles
+    }
+
+    pub fn set_visible(&mut self, handle: BlipHandle, visible: bool) -> VcmpResult<()> {
+        let blip = self.blips.get_mut(&handle).ok_or(VcmpError::NoSuchEntity)?;
+        match (visible, blip.marker) {
+            (true, None) => blip.marker = Some(blip.definition.create()?),
+            (false, Some(marker)) => {
+                blip.marker = None;
+                let _ = vcmp_func().destroy_marker(marker);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    pub fn show_group(&mut self, group: &str) -> VcmpResult<()> {
+        for handle in self.group(group) {
+            self.set_visible(handle, true)?;
+        }
+        Ok(())
+    }
+
+    pub fn hide_group(&mut self, group: &str) {
+        for handle in self.group(group) {
+            let _ = self.set_visible(handle, false);
+        }
+    }
+
+    /// 删掉整组, 返回删掉的数量
+    pub fn remove_group(&mut self, group: &str) -> usize {
+        let handles = self.group(group);
+        for &handle in &handles {
+            self.remove(handle);
+        }
+        handles.len()
+    }
+
+    /// 修改参数, 显示中的图标会被重建
+    pub fn update(
+        &mut self,
+        handle: BlipHandle,
+        update: impl FnOnce(&mut BlipDefinition),
+    ) -> VcmpResult<()> {
+        let blip = self.blips.get_mut(&handle).ok_or(VcmpError::NoSuchEntity)?;
+        update(&mut blip.definition);
+        if let Some(marker) = blip.marker.take() {
+            let _ = vcmp_func().destroy_marker(marker);
+            blip.marker = Some(blip.definition.create()?);
+        }
+        Ok(())
+    }
+
+    pub fn set_world(&mut self, handle: BlipHandle, world: i32) -> VcmpResult<()> {
+        self.update(handle, |definition| definition.world = world)
+    }
+
+    pub fn set_position(&mut self, handle: BlipHandle, position: Vectorf32) -> VcmpResult<()> {
+        self.update(handle, |definition| definition.position = position)
+    }
+
+    /// 重建所有应该显示但是已经不存在的图标, 返回重建的数量
+    pub fn refresh(&mut self) -> usize {
+        let func = vcmp_func();
+        let mut recreated = 0;
+        for blip in self.blips.values_mut() {
+            let Some(marker) = blip.marker else {
+                continue;
+            };
+            if func.is_marker_alive(marker) {
+                continue;
+            }
+            blip.marker = blip.definition.create().ok();
+            if blip.marker.is_some() {
+                recreated += 1;
+            }
+        }
+        recreated
+    }
+}
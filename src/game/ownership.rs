@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+
+use crate::events::player::PlayerDisconnectEvent;
+use crate::func::CheckPointMethods;
+use crate::game::GameEntity;
+use crate::{CheckpointId, PlayerId, vcmp_func};
+
+/// 实体属于谁
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Owner {
+    /// 玩家断开时清理
+    Player(PlayerId),
+    /// 自己起名的一组实体, 需要手动 `release`
+    Session(String),
+}
+
+impl From<PlayerId> for Owner {
+    fn from(value: PlayerId) -> Self {
+        Owner::Player(value)
+    }
+}
+
+impl From<&str> for Owner {
+    fn from(value: &str) -> Self {
+        Owner::Session(value.to_string())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct OwnedEntity {
+    owner: Owner,
+    cleanup: bool,
+}
+
+/// 记录实体属于哪个玩家 / 会话, 玩家断开时删掉他的实体
+///
+/// 每个实体默认都会被清理, 可以用 `set_cleanup` 单独关掉, 关掉的实体在清理时只会被
+/// 取消记录. 在别的地方删掉的实体请 `untag`, 不然 id 被复用之后可能删错
+///
+/// 需要在 `PlayerDisconnect` 里调用对应的方法
+#[derive(Debug, Clone, Default)]
+pub struct OwnershipTracker {
+    entities: HashMap<GameEntity, OwnedEntity>,
+}
+
+impl OwnershipTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录实体的主人, 已经有主人的会被替换
+    pub fn tag(&mut self, entity: GameEntity, owner: impl Into<Owner>) {
+        self.entities.insert(
+            entity,
+            OwnedEntity {
+                owner: owner.into(),
+                cleanup: true,
+            },
+        );
+    }
+
+    /// 按 `get_checkpoint_owner` 记录, 没有主人的检查点返回 false
+    pub fn tag_checkpoint(&mut self, checkpoint_id: CheckpointId) -> bool {
+        let owner = vcmp_func().get_checkpoint_owner(checkpoint_id);
+        if owner < 0 {
+            return false;
+        }
+        self.tag(GameEntity::Checkpoint(checkpoint_id), owner);
+        true
+    }
+
+    pub fn untag(&mut self, entity: GameEntity) -> Option<Owner> {
+        self.entities.remove(&entity).map(|owned| owned.owner)
+    }
+
+    /// 清理时是否删掉这个实体, 没有记录的实体返回 false
+    pub fn set_cleanup(&mut self, entity: GameEntity, cleanup: bool) -> bool {
+        match self.entities.get_mut(&entity) {
+            Some(owned) => {
+                owned.cleanup = cleanup;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn owner_of(&self, entity: GameEntity) -> Option<&Owner> {
+        self.entities.get(&entity).map(|owned| &owned.owner)
+    }
+
+    pub fn owned_by(&self, owner: &Owner) -> Vec<GameEntity> {
+        self.entities
+            .iter()
+            .filter(|(_, owned)| owned.owner == *owner)
+            .map(|(&entity, _)| entity)
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    /// 删掉这个主人的所有实体, 返回删掉的数量
+    pub fn release(&mut self, owner: &Owner) -> usize {
+        let owned = self.owned_by(owner);
+        let mut destroyed = 0;
+        for entity in owned {
+            let Some(owned) = self.entities.remove(&entity) else {
+                continue;
+            };
+            if owned.cleanup && entity.destroy().is_ok() {
+                destroyed += 1;
+            }
+        }
+        destroyed
+    }
+
+    pub fn on_player_disconnect(&mut self, event: &PlayerDisconnectEvent) {
+        self.release(&Owner::Player(event.player_id));
+    }
+}
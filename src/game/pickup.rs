@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::events::pickup::PickupPickedEvent;
+use crate::events::server::ServerFrameEvent;
+use crate::func::{PickupMethods, PlayerMethods};
+use crate::game::FrameClock;
+use crate::utils::Vectorf32;
+use crate::{PlayerId, VcmpError, VcmpResult, WeaponId, vcmp_func};
+
+/// 捡起之后的效果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PickupKind {
+    Weapon {
+        weapon: WeaponId,
+        ammo: i32,
+    },
+    /// 加血, 最多加到 100
+    Health(f32),
+    /// 加甲, 最多加到 100
+    Armour(f32),
+    Money(i32),
+    /// 什么都不做, 只调用 handler
+    Custom,
+}
+
+impl PickupKind {
+    fn apply(&self, player_id: PlayerId) -> VcmpResult<()> {
+        let func = vcmp_func();
+        match *self {
+            PickupKind::Weapon { weapon, ammo } => func.give_player_weapon(player_id, weapon, ammo),
+            PickupKind::Health(amount) => {
+                let health = (func.get_player_health(player_id) + amount).min(100.0);
+                func.set_player_health(player_id, health)
+            }
+            PickupKind::Armour(amount) => {
+                let armour = (func.get_player_armour(player_id) + amount).min(100.0);
+                func.set_player_armour(player_id, armour)
+            }
+            PickupKind::Money(amount) => func.give_player_money(player_id, amount),
+            PickupKind::Custom => Ok(()),
+        }
+    }
+}
+
+/// 捡起之后怎么重新出现
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RespawnPolicy {
+    /// 捡起之后删掉
+    Never,
+    /// 交给服务器的自动计时 (`set_pickup_auto_timer`)
+    #[default]
+    Auto,
+    /// 服务器自动计时, 指定时长
+    AutoAfter(Duration),
+    /// 过一段时间由这里调用 `refresh_pickup`
+    RefreshAfter(Duration),
+}
+
+/// 创建 pickup 用的参数
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PickupSpec {
+    pub kind: PickupKind,
+    pub model: i32,
+    pub world: i32,
+    pub quantity: i32,
+    pub position: Vectorf32,
+    pub alpha: i32,
+    pub respawn: RespawnPolicy,
+}
+
+impl PickupSpec {
+    pub fn new(kind: PickupKind, model: i32, world: i32, position: Vectorf32) -> Self {
+        Self {
+            kind,
+            model,
+            world,
+            quantity: 1,
+            position,
+            alpha: 255,
+            respawn: RespawnPolicy::Auto,
+        }
+    }
+
+    pub fn quantity(mut self, quantity: i32) -> Self {
+        self.quantity = quantity;
+        self
+    }
+
+    pub fn alpha(mut self, alpha: i32) -> Self {
+        self.alpha = alpha;
+        self
+    }
+
+    pub fn respawn(mut self, respawn: RespawnPolicy) -> Self {
+        self.respawn = respawn;
+        self
+    }
+}
+
+type PickupHandler = Box<dyn FnMut(PlayerId, i32) + Send>;
+
+struct ManagedPickup {
+    spec: PickupSpec,
+    handler: Option<PickupHandler>,
+    /// `RefreshAfter` 时下次刷新的时间
+    refresh_at: Option<Duration>,
+}
+
+/// 按类型管理 pickup
+///
+/// 捡起时会自动给效果, 然后调用这个 pickup 自己的 handler
+///
+/// 需要在 `PickupPicked` 和 `ServerFrame` 里调用对应的方法
+#[derive(Default)]
+pub struct PickupManager {
+    clock: FrameClock,
+    pickups: HashMap<i32, ManagedPickup>,
+}
+
+impl PickupManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 创建 pickup, 返回 id
+    pub fn create(&mut self, spec: PickupSpec) -> VcmpResult<i32> {
+        let func = vcmp_func();
+        let automatic = matches!(
+            spec.respawn,
+            RespawnPolicy::Auto | RespawnPolicy::AutoAfter(_)
+        );
+        let pickup_id = func.create_pickup(
+            spec.model,
+            spec.world,
+            spec.quantity,
+            spec.position,
+            spec.alpha,
+            automatic,
+        );
+        if pickup_id < 0 {
+            return Err(VcmpError::PoolExhausted);
+        }
+        if let RespawnPolicy::AutoAfter(duration) = spec.respawn
+            && let Err(err) = func.set_pickup_auto_timer(pickup_id, duration.as_millis() as u32)
+        {
+            let _ = func.delete_pickup(pickup_id);
+            return Err(err);
+        }
+        self.pickups.insert(
+            pickup_id,
+            ManagedPickup {
+                spec,
+                handler: None,
+                refresh_at: None,
+            },
+        );
+        Ok(pickup_id)
+    }
+
+    /// 创建 pickup 并设置 handler
+    pub fn create_with(
+        &mut self,
+        spec: PickupSpec,
+        handler: impl FnMut(PlayerId, i32) + Send + 'static,
+    ) -> VcmpResult<i32> {
+        let pickup_id = self.create(spec)?;
+        self.set_handler(pickup_id, handler);
+        Ok(pickup_id)
+    }
+
+    /// 捡起时调用, 参数是玩家和 pickup id. 不是这里管理的 pickup 返回 false
+    pub fn set_handler(
+        &mut self,
+        pickup_id: i32,
+        handler: impl FnMut(PlayerId, i32) + Send + 'static,
+    ) -> bool {
+        match self.pickups.get_mut(&pickup_id) {
+            Some(pickup) => {
+                pickup.handler = Some(Box::new(handler));
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn spec(&self, pickup_id: i32) -> Option<&PickupSpec> {
+        self.pickups.get(&pickup_id).map(|pickup| &pickup.spec)
+    }
+
+    pub fn contains(&self, pickup_id: i32) -> bool {
+        self.pickups.contains_key(&pickup_id)
+    }
+
+    pub fn ids(&self) -> Vec<i32> {
+        let mut ids: Vec<i32> = self.pickups.keys().copied().collect();
+        ids.sort();
+        ids
+    }
+
+    /// 删掉 pickup
+    pub fn remove(&mut self, pickup_id: i32) -> bool {
+        if self.pickups.remove(&pickup_id).is_none() {
+            return false;
+        }
+        let _ = vcmp_func().delete_pickup(pickup_id);
+        true
+    }
+
+    pub fn clear(&mut self) {
+        let func = vcmp_func();
+        for pickup_id in self.pickups.drain().map(|(id, _)| id) {
+            let _ = func.delete_pickup(pickup_id);
+        }
+    }
+
+    /// 给效果并调用 handler, 不是这里管理的 pickup 返回 false
+    pub fn on_pickup_picked(&mut self, event: &PickupPickedEvent) -> bool {
+        let Some(pickup) = self.pickups.get_mut(&event.pickup_id) else {
+            return false;
+        };
+        let _ = pickup.spec.kind.apply(event.player_id);
+        if let Some(handler) = pickup.handler.as_mut() {
+            handler(event.player_id, event.pickup_id);
+        }
+        match pickup.spec.respawn {
+            RespawnPolicy::Never => {
+                self.remove(event.pickup_id);
+            }
+            RespawnPolicy::RefreshAfter(duration) => {
+                pickup.refresh_at = Some(self.clock.now() + duration);
+            }
+            RespawnPolicy::Auto | RespawnPolicy::AutoAfter(_) => {}
+        }
+        true
+    }
+
+    /// 处理 `RefreshAfter`
+    pub fn on_server_frame(&mut self, event: &ServerFrameEvent) {
+        self.clock.advance(event);
+        let now = self.clock.now();
+        let func = vcmp_func();
+        for (&pickup_id, pickup) in self.pickups.iter_mut() {
+            if pickup.refresh_at.is_some_and(|at| at <= now) {
+                pickup.refresh_at = None;
+                let _ = func.refresh_pickup(pickup_id);
+            }
+        }
+    }
+}
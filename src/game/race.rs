@@ -0,0 +1,446 @@
+use std::time::Duration;
+
+use crate::catalog::{AnnounceStyle, BlipSprite};
+use crate::events::checkpoint::CheckpointEnteredEvent;
+use crate::events::player::PlayerDisconnectEvent;
+use crate::events::server::ServerFrameEvent;
+use crate::func::{CheckPointMethods, MarkerMethods, PlayerMethods};
+use crate::game::FrameClock;
+use crate::utils::{Color, Vectorf32};
+use crate::{PlayerId, vcmp_func};
+
+/// 赛道上的一个检查点
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrackCheckpoint {
+    pub position: Vectorf32,
+    pub radius: f32,
+    /// 小地图图标, None 表示不显示
+    pub blip: Option<BlipSprite>,
+}
+
+impl TrackCheckpoint {
+    pub fn new(position: Vectorf32, radius: f32) -> Self {
+        Self {
+            position,
+            radius,
+            blip: None,
+        }
+    }
+
+    pub fn with_blip(mut self, sprite: BlipSprite) -> Self {
+        self.blip = Some(sprite);
+        self
+    }
+}
+
+/// 一条赛道, 按顺序经过所有检查点算一圈
+#[derive(Debug, Clone, PartialEq)]
+pub struct RaceTrack {
+    pub name: String,
+    pub world: i32,
+    pub checkpoints: Vec<TrackCheckpoint>,
+    pub laps: u32,
+    pub color: Color,
+    pub is_sphere: bool,
+}
+
+impl RaceTrack {
+    pub fn new(name: &str, world: i32) -> Self {
+        Self {
+            name: name.to_string(),
+            world,
+            checkpoints: Vec::new(),
+            laps: 1,
+            color: Color::from_rgb(0xFFD700, None),
+            is_sphere: false,
+        }
+    }
+
+    pub fn checkpoint(mut self, checkpoint: TrackCheckpoint) -> Self {
+        self.checkpoints.push(checkpoint);
+        self
+    }
+
+    pub fn laps(mut self, laps: u32) -> Self {
+        self.laps = laps.max(1);
+        self
+    }
+
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn sphere(mut self, is_sphere: bool) -> Self {
+        self.is_sphere = is_sphere;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RaceState {
+    /// 还在报名
+    Waiting,
+    /// 倒计时中, 剩余时间
+    Countdown(Duration),
+    Running,
+    Ended,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RaceEvent {
+    /// 倒计时的每一秒, 剩余秒数
+    Countdown(u32),
+    Started,
+    /// 经过一个检查点, `split` 是从开始到现在的时间
+    Checkpoint {
+        player_id: PlayerId,
+        index: usize,
+        lap: u32,
+        split: Duration,
+    },
+    /// 跑完一圈, `lap` 从 1 开始
+    Lap {
+        player_id: PlayerId,
+        lap: u32,
+        time: Duration,
+    },
+    /// `position` 从 1 开始
+    Finished {
+        player_id: PlayerId,
+        position: usize,
+        time: Duration,
+    },
+    /// 玩家断开或者被移出比赛
+    Left {
+        player_id: PlayerId,
+    },
+    Ended,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RaceResult {
+    pub player_id: PlayerId,
+    pub position: usize,
+    pub time: Duration,
+}
+
+#[derive(Debug, Clone)]
+struct Racer {
+    player_id: PlayerId,
+    /// 当前要去的检查点
+    next: usize,
+    lap: u32,
+    checkpoint_id: Option<i32>,
+    splits: Vec<Duration>,
+    lap_started: Duration,
+    finished: Option<Duration>,
+}
+
+type RaceCallback = Box<dyn FnMut(&RaceEvent) + Send>;
+
+/// 一场检查点比赛
+///
+/// 每个选手只能看到自己的下一个检查点 (创建时带上了 owner). 开始之后会给赛道上
+/// 设置了 `blip` 的检查点创建小地图图标, 这些图标是整个世界可见的
+///
+/// 需要在 `ServerFrame` `CheckpointEntered` `PlayerDisconnect` 里调用对应的方法
+pub struct Race {
+    track: RaceTrack,
+    state: RaceState,
+    clock: FrameClock,
+    started_at: Duration,
+    racers: Vec<Racer>,
+    results: Vec<RaceResult>,
+    blips: Vec<i32>,
+    announce_countdown: bool,
+    on_event: Option<RaceCallback>,
+}
+
+impl Race {
+    pub fn new(track: RaceTrack) -> Self {
+        Self {
+            track,
+            state: RaceState::Waiting,
+            clock: FrameClock::new(),
+            started_at: Duration::ZERO,
+            racers: Vec::new(),
+            results: Vec::new(),
+            blips: Vec::new(),
+            announce_countdown: true,
+            on_event: None,
+        }
+    }
+
+    pub fn track(&self) -> &RaceTrack {
+        &self.track
+    }
+
+    pub fn state(&self) -> RaceState {
+        self.state
+    }
+
+    pub fn on_event(&mut self, callback: impl FnMut(&RaceEvent) + Send + 'static) {
+        self.on_event = Some(Box::new(callback));
+    }
+
+    /// 倒计时时是否给选手发公告, 默认发
+    pub fn set_announce_countdown(&mut self, announce: bool) {
+        self.announce_countdown = announce;
+    }
+
+    /// 只能在开始之前加入
+    pub fn add_racer(&mut self, player_id: PlayerId) -> bool {
+        if self.state != RaceState::Waiting || self.is_racer(player_id) {
+            return false;
+        }
+        self.racers.push(Racer {
+            player_id,
+            next: 0,
+            lap: 0,
+            checkpoint_id: None,
+            splits: Vec::new(),
+            lap_started: Duration::ZERO,
+            finished: None,
+        });
+        true
+    }
+
+    /// 移出比赛并删掉他的检查点
+    pub fn remove_racer(&mut self, player_id: PlayerId) -> bool {
+        let Some(index) = self
+            .racers
+            .iter()
+            .position(|racer| racer.player_id == player_id)
+        else {
+            return false;
+        };
+        let racer = self.racers.remove(index);
+        if let Some(checkpoint_id) = racer.checkpoint_id {
+            let _ = vcmp_func().delete_checkpoint(checkpoint_id);
+        }
+        self.emit(RaceEvent::Left { player_id });
+        self.end_if_done();
+        true
+    }
+
+    pub fn is_racer(&self, player_id: PlayerId) -> bool {
+        self.racers.iter().any(|racer| racer.player_id == player_id)
+    }
+
+    pub fn racers(&self) -> Vec<PlayerId> {
+        self.racers.iter().map(|racer| racer.player_id).collect()
+    }
+
+    /// 当前的圈数 (从 0 开始) 和下一个检查点
+    pub fn progress(&self, player_id: PlayerId) -> Option<(u32, usize)> {
+        self.racer(player_id).map(|racer| (racer.lap, racer.next))
+    }
+
+    /// 每个经过的检查点相对开始的时间
+    pub fn splits(&self, player_id: PlayerId) -> Option<&[Duration]> {
+        self.racer(player_id).map(|racer| racer.splits.as_slice())
+    }
+
+    /// 按名次排好的成绩
+    pub fn results(&self) -> &[RaceResult] {
+        &self.results
+    }
+
+    /// 从开始到现在的时间
+    pub fn elapsed(&self) -> Duration {
+        match self.state {
+            RaceState::Running | RaceState::Ended => {
+                self.clock.now().saturating_sub(self.started_at)
+            }
+            _ => Duration::ZERO,
+        }
+    }
+
+    /// 开始倒计时, 倒计时为 0 时直接开始. 没有检查点或者没有选手时返回 false
+    pub fn start(&mut self, countdown: Duration) -> bool {
+        if self.state != RaceState::Waiting
+            || self.track.checkpoints.is_empty()
+            || self.racers.is_empty()
+        {
+            return false;
+        }
+        if countdown.is_zero() {
+            self.begin();
+        } else {
+            self.state = RaceState::Countdown(countdown);
+            self.tick_countdown(countdown.as_secs_f32().ceil() as u32);
+        }
+        true
+    }
+
+    /// 结束比赛, 删掉所有检查点和图标
+    pub fn end(&mut self) {
+        if self.state == RaceState::Ended {
+            return;
+        }
+        let func = vcmp_func();
+        for racer in &mut self.racers {
+            if let Some(checkpoint_id) = racer.checkpoint_id.take() {
+                let _ = func.delete_checkpoint(checkpoint_id);
+            }
+        }
+        for blip in self.blips.drain(..) {
+            let _ = func.destroy_marker(blip);
+        }
+        self.state = RaceState::Ended;
+        self.emit(RaceEvent::Ended);
+    }
+
+    pub fn on_server_frame(&mut self, event: &ServerFrameEvent) {
+        let delta = self.clock.advance(event);
+        let RaceState::Countdown(remaining) = self.state else {
+            return;
+        };
+        let left = remaining.saturating_sub(delta);
+        if left.is_zero() {
+            self.begin();
+            return;
+        }
+        self.state = RaceState::Countdown(left);
+        let before = remaining.as_secs_f32().ceil() as u32;
+        let after = left.as_secs_f32().ceil() as u32;
+        if after < before {
+            self.tick_countdown(after);
+        }
+    }
+
+    /// 是这场比赛的检查点时返回 true
+    pub fn on_checkpoint_entered(&mut self, event: &CheckpointEnteredEvent) -> bool {
+        if self.state != RaceState::Running {
+            return false;
+        }
+        let Some(index) = self.racers.iter().position(|racer| {
+            racer.player_id == event.player_id && racer.checkpoint_id == Some(event.checkpoint_id)
+        }) else {
+            return false;
+        };
+        let now = self.elapsed();
+        let count = self.track.checkpoints.len();
+        let laps = self.track.laps;
+        let racer = &mut self.racers[index];
+        let _ = vcmp_func().delete_checkpoint(event.checkpoint_id);
+        racer.checkpoint_id = None;
+        racer.splits.push(now);
+
+        let mut events = vec![RaceEvent::Checkpoint {
+            player_id: racer.player_id,
+            index: racer.next,
+            lap: racer.lap,
+            split: now,
+        }];
+        racer.next += 1;
+        if racer.next == count {
+            racer.next = 0;
+            racer.lap += 1;
+            events.push(RaceEvent::Lap {
+                player_id: racer.player_id,
+                lap: racer.lap,
+                time: now - racer.lap_started,
+            });
+            racer.lap_started = now;
+        }
+        if racer.lap >= laps {
+            racer.finished = Some(now);
+            let result = RaceResult {
+                player_id: racer.player_id,
+                position: self.results.len() + 1,
+                time: now,
+            };
+            self.results.push(result);
+            events.push(RaceEvent::Finished {
+                player_id: result.player_id,
+                position: result.position,
+                time: now,
+            });
+        } else {
+            Self::show_checkpoint(&self.track, racer);
+        }
+
+        for event in &events {
+            self.emit(event.clone());
+        }
+        self.end_if_done();
+        true
+    }
+
+    pub fn on_player_disconnect(&mut self, event: &PlayerDisconnectEvent) {
+        self.remove_racer(event.player_id);
+    }
+
+    fn racer(&self, player_id: PlayerId) -> Option<&Racer> {
+        self.racers
+            .iter()
+            .find(|racer| racer.player_id == player_id)
+    }
+
+    fn begin(&mut self) {
+        self.state = RaceState::Running;
+        self.started_at = self.clock.now();
+        let func = vcmp_func();
+        for checkpoint in &self.track.checkpoints {
+            if let Some(sprite) = checkpoint.blip {
+                self.blips.push(func.create_marker(
+                    self.track.world,
+                    checkpoint.position,
+                    1,
+                    self.track.color,
+                    sprite.into(),
+                    None,
+                ));
+            }
+        }
+        for racer in &mut self.racers {
+            Self::show_checkpoint(&self.track, racer);
+        }
+        if self.announce_countdown {
+            self.announce("GO!");
+        }
+        self.emit(RaceEvent::Started);
+    }
+
+    fn tick_countdown(&mut self, seconds: u32) {
+        if self.announce_countdown {
+            self.announce(&seconds.to_string());
+        }
+        self.emit(RaceEvent::Countdown(seconds));
+    }
+
+    fn announce(&self, message: &str) {
+        let func = vcmp_func();
+        for racer in &self.racers {
+            let _ = func.send_announce(racer.player_id, AnnounceStyle::BigMessage, message);
+        }
+    }
+
+    fn show_checkpoint(track: &RaceTrack, racer: &mut Racer) {
+        let checkpoint = track.checkpoints[racer.next];
+        racer.checkpoint_id = Some(vcmp_func().create_checkpoint(
+            Some(racer.player_id),
+            track.world,
+            track.is_sphere,
+            checkpoint.position,
+            track.color,
+            checkpoint.radius,
+        ));
+    }
+
+    /// 开始之后所有选手都跑完 (或者都走了) 就结束
+    fn end_if_done(&mut self) {
+        let running = matches!(self.state, RaceState::Running | RaceState::Countdown(_));
+        if running && self.racers.iter().all(|racer| racer.finished.is_some()) {
+            self.end();
+        }
+    }
+
+    fn emit(&mut self, event: RaceEvent) {
+        if let Some(callback) = self.on_event.as_mut() {
+            callback(&event);
+        }
+    }
+}
@@ -0,0 +1,288 @@
+use std::collections::HashMap;
+
+use crate::catalog::KeyCode;
+use crate::events::player::{
+    PlayerDeathEvent, PlayerDisconnectEvent, PlayerKeyBindDownEvent, PlayerSpectateEvent,
+};
+use crate::func::PlayerMethods;
+use crate::game::KeyBindManager;
+use crate::utils::Vectorf32;
+use crate::{PlayerId, VcmpResult, vcmp_func};
+
+const NEXT_BIND: &str = "spectate_next";
+const PREVIOUS_BIND: &str = "spectate_previous";
+const EXIT_BIND: &str = "spectate_exit";
+
+/// 可以观战哪些玩家
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SpectateFilter {
+    #[default]
+    Any,
+    /// 和观战的人同队
+    SameTeam,
+    /// 和观战的人在开始观战时的同一个世界
+    SameWorld,
+    Team(i32),
+    World(i32),
+}
+
+/// 开始观战前的状态, 退出时恢复
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SavedState {
+    world: i32,
+    team: i32,
+    position: Option<Vectorf32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Session {
+    filter: SpectateFilter,
+    target: Option<PlayerId>,
+    saved: SavedState,
+}
+
+/// 观战
+///
+/// 目标按玩家 id 排序循环切换, 会跳过没出生 / 已经死了的玩家和其他正在观战的人.
+/// 目标断开或者死掉时自动换到下一个, 没有目标时保持观战状态, 等下次 `next`
+///
+/// 退出时恢复开始观战前的世界和位置, 并调用 `restore_camera`
+///
+/// 需要在 `PlayerKeyBindDown` `PlayerDeath` `PlayerSpectate` `PlayerDisconnect`
+/// 里调用对应的方法
+#[derive(Debug, Clone, Default)]
+pub struct SpectateController {
+    sessions: HashMap<PlayerId, Session>,
+}
+
+impl SpectateController {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 用 `KeyBindManager` 注册切换 / 退出用的按键
+    pub fn register_keys(
+        &self,
+        binds: &mut KeyBindManager,
+        next: KeyCode,
+        previous: KeyCode,
+        exit: KeyCode,
+    ) -> VcmpResult<()> {
+        binds.register(NEXT_BIND, &[next], false)?;
+        binds.register(PREVIOUS_BIND, &[previous], false)?;
+        binds.register(EXIT_BIND, &[exit], false)?;
+        Ok(())
+    }
+
+    /// 开始观战, 返回第一个目标. 已经在观战时只换过滤条件
+    pub fn start(
+        &mut self,
+        player_id: PlayerId,
+        filter: SpectateFilter,
+    ) -> VcmpResult<Option<PlayerId>> {
+        if let Some(session) = self.sessions.get_mut(&player_id) {
+            session.filter = filter;
+        } else {
+            let func = vcmp_func();
+            let saved = SavedState {
+                world: func.get_player_world(player_id),
+                team: func.get_player_team(player_id),
+                position: func.get_player_position(player_id).ok(),
+            };
+            self.sessions.insert(
+                player_id,
+                Session {
+                    filter,
+                    target: None,
+                    saved,
+                },
+            );
+        }
+        self.next(player_id)
+    }
+
+    /// 退出观战, 不在观战时返回 false
+    ///
+    /// 每一步恢复都会尝试, 有失败的时候返回第一个错误
+    pub fn stop(&mut self, player_id: PlayerId) -> VcmpResult<bool> {
+        let Some(session) = self.sessions.remove(&player_id) else {
+            return Ok(false);
+        };
+        let func = vcmp_func();
+        let results = [
+            func.set_player_spectate_target(player_id, -1),
+            func.set_player_world(player_id, session.saved.world),
+            session.saved.position.map_or(Ok(()), |position| {
+                func.set_player_position(player_id, position)
+            }),
+            func.restore_camera(player_id),
+        ];
+        results.into_iter().collect::<VcmpResult<()>>()?;
+        Ok(true)
+    }
+
+    pub fn stop_all(&mut self) {
+        let players: Vec<PlayerId> = self.sessions.keys().copied().collect();
+        for player_id in players {
+            let _ = self.stop(player_id);
+        }
+    }
+
+    pub fn is_spectating(&self, player_id: PlayerId) -> bool {
+        self.sessions.contains_key(&player_id)
+    }
+
+    pub fn target_of(&self, player_id: PlayerId) -> Option<PlayerId> {
+        self.sessions.get(&player_id)?.target
+    }
+
+    /// 正在看这个玩家的人
+    pub fn spectators_of(&self, target_id: PlayerId) -> Vec<PlayerId> {
+        let mut spectators: Vec<PlayerId> = self
+            .sessions
+            .iter()
+            .filter(|(_, session)| session.target == Some(target_id))
+            .map(|(&player_id, _)| player_id)
+            .collect();
+        spectators.sort();
+        spectators
+    }
+
+    /// 现在可以看的目标, 按 id 排序
+    pub fn targets(&self, player_id: PlayerId) -> Vec<PlayerId> {
+        let Some(session) = self.sessions.get(&player_id) else {
+            return Vec::new();
+        };
+        let func = vcmp_func();
+        let mut targets: Vec<PlayerId> = func
+            .get_connected_players()
+            .into_iter()
+            .filter(|&target| target != player_id && !self.sessions.contains_key(&target))
+            .filter(|&target| {
+                func.is_player_spawned(target) && func.get_player_health(target) > 0.0
+            })
+            .filter(|&target| match session.filter {
+                SpectateFilter::Any => true,
+                SpectateFilter::SameTeam => func.get_player_team(target) == session.saved.team,
+                SpectateFilter::SameWorld => func.get_player_world(target) == session.saved.world,
+                SpectateFilter::Team(team) => func.get_player_team(target) == team,
+                SpectateFilter::World(world) => func.get_player_world(target) == world,
+            })
+            .collect();
+        targets.sort();
+        targets
+    }
+
+    /// 看指定的玩家, 不管过滤条件. 不在观战时返回 false
+    pub fn spectate(&mut self, player_id: PlayerId, target_id: PlayerId) -> VcmpResult<bool> {
+        let Some(session) = self.sessions.get_mut(&player_id) else {
+            return Ok(false);
+        };
+        let func = vcmp_func();
+        // 观战的人要在目标的世界里才看得到
+        func.set_player_world(player_id, func.get_player_world(target_id))?;
+        func.set_player_spectate_target(player_id, target_id)?;
+        session.target = Some(target_id);
+        Ok(true)
+    }
+
+    /// 换到下一个目标
+    pub fn next(&mut self, player_id: PlayerId) -> VcmpResult<Option<PlayerId>> {
+        self.cycle(player_id, true)
+    }
+
+    /// 换到上一个目标
+    pub fn previous(&mut self, player_id: PlayerId) -> VcmpResult<Option<PlayerId>> {
+        self.cycle(player_id, false)
+    }
+
+    /// 处理 `register_keys` 注册的按键, 不是这些按键时返回 false
+    pub fn on_key_bind_down(
+        &mut self,
+        event: &PlayerKeyBindDownEvent,
+        binds: &KeyBindManager,
+    ) -> bool {
+        if !self.is_spectating(event.player_id) {
+            return false;
+        }
+        let _ = match binds.name_of(event.bind_id) {
+            Some(NEXT_BIND) => self.next(event.player_id).map(|_| ()),
+            Some(PREVIOUS_BIND) => self.previous(event.player_id).map(|_| ()),
+            Some(EXIT_BIND) => self.stop(event.player_id).map(|_| ()),
+            _ => return false,
+        };
+        true
+    }
+
+    pub fn on_player_death(&mut self, event: &PlayerDeathEvent) {
+        self.skip_target(event.player_id);
+    }
+
+    /// 目标被别的代码改掉时跟着更新
+    pub fn on_player_spectate(&mut self, event: &PlayerSpectateEvent) {
+        if let Some(session) = self.sessions.get_mut(&event.player_id) {
+            session.target = (event.target_id >= 0).then_some(event.target_id);
+        }
+    }
+
+    pub fn on_player_disconnect(&mut self, event: &PlayerDisconnectEvent) {
+        self.sessions.remove(&event.player_id);
+        self.skip_target(event.player_id);
+    }
+
+    fn skip_target(&mut self, target_id: PlayerId) {
+        for player_id in self.spectators_of(target_id) {
+            if let Some(session) = self.sessions.get_mut(&player_id) {
+                session.target = None;
+            }
+            // 断开 / 死亡回调里这个目标可能还算在线, 要手动跳过
+            let _ = self.cycle_from(player_id, target_id, true, true);
+        }
+    }
+
+    fn cycle(&mut self, player_id: PlayerId, forward: bool) -> VcmpResult<Option<PlayerId>> {
+        let Some(current) = self.target_of(player_id) else {
+            let from = if forward { -1 } else { PlayerId::MAX };
+            return self.cycle_from(player_id, from, forward, false);
+        };
+        self.cycle_from(player_id, current, forward, false)
+    }
+
+    /// 从 `from` 开始找下一个 / 上一个目标, 到头了绕回去. `skip_from` 时不会选回 `from`
+    fn cycle_from(
+        &mut self,
+        player_id: PlayerId,
+        from: PlayerId,
+        forward: bool,
+        skip_from: bool,
+    ) -> VcmpResult<Option<PlayerId>> {
+        let mut targets = self.targets(player_id);
+        if skip_from {
+            targets.retain(|&target| target != from);
+        }
+        let target = if forward {
+            targets
+                .iter()
+                .find(|&&target| target > from)
+                .or(targets.first())
+        } else {
+            targets
+                .iter()
+                .rev()
+                .find(|&&target| target < from)
+                .or(targets.last())
+        };
+        match target.copied() {
+            Some(target) => {
+                self.spectate(player_id, target)?;
+                Ok(Some(target))
+            }
+            None => {
+                if let Some(session) = self.sessions.get_mut(&player_id) {
+                    session.target = None;
+                }
+                Ok(None)
+            }
+        }
+    }
+}
@@ -0,0 +1,291 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use crate::catalog::AnnounceStyle;
+use crate::events::player::{PlayerDisconnectEvent, PlayerMessageEvent, PlayerSpawnEvent};
+use crate::func::PlayerMethods;
+use crate::options::VcmpServerOption;
+use crate::utils::Color;
+use crate::{PlayerId, VcmpError, vcmp_func};
+
+/// 一个队伍, `id` 就是 `set_player_team` 用的队伍号
+#[derive(Debug, Clone, PartialEq)]
+pub struct Team {
+    pub id: i32,
+    pub name: String,
+    pub color: Color,
+    /// None 表示不限人数
+    pub max_players: Option<usize>,
+}
+
+impl Team {
+    pub fn new(id: i32, name: &str, color: Color) -> Self {
+        Self {
+            id,
+            name: name.to_string(),
+            color,
+            max_players: None,
+        }
+    }
+
+    pub fn max_players(mut self, max_players: usize) -> Self {
+        self.max_players = Some(max_players);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TeamError {
+    NoSuchTeam(i32),
+    Full(i32),
+    Vcmp(VcmpError),
+}
+
+impl std::error::Error for TeamError {}
+
+impl Display for TeamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TeamError::NoSuchTeam(id) => write!(f, "队伍 {id} 不存在"),
+            TeamError::Full(id) => write!(f, "队伍 {id} 已满"),
+            TeamError::Vcmp(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl From<VcmpError> for TeamError {
+    fn from(value: VcmpError) -> Self {
+        TeamError::Vcmp(value)
+    }
+}
+
+pub type TeamResult<T> = Result<T, TeamError>;
+
+/// 队伍管理
+///
+/// 玩家换队时会同时设置 `set_player_team` 和 `set_player_color`, 出生时再设置一次,
+/// 免得被职业自带的队伍覆盖
+///
+/// 需要在 `PlayerSpawn` `PlayerMessage` `PlayerDisconnect` 里调用对应的方法
+#[derive(Debug)]
+pub struct TeamManager {
+    teams: Vec<Team>,
+    members: HashMap<PlayerId, i32>,
+    /// 加入队伍的顺序, 平衡时最后加入的人先走
+    joined: HashMap<PlayerId, u64>,
+    next_join: u64,
+    chat_prefix: Option<String>,
+}
+
+impl Default for TeamManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TeamManager {
+    pub fn new() -> Self {
+        Self {
+            teams: Vec::new(),
+            members: HashMap::new(),
+            joined: HashMap::new(),
+            next_join: 0,
+            chat_prefix: Some("!".to_string()),
+        }
+    }
+
+    /// 同一个 id 的队伍会被替换
+    pub fn add_team(&mut self, team: Team) {
+        match self
+            .teams
+            .iter_mut()
+            .find(|existing| existing.id == team.id)
+        {
+            Some(existing) => *existing = team,
+            None => self.teams.push(team),
+        }
+    }
+
+    pub fn team(&self, team_id: i32) -> Option<&Team> {
+        self.teams.iter().find(|team| team.id == team_id)
+    }
+
+    pub fn teams(&self) -> &[Team] {
+        &self.teams
+    }
+
+    /// 按名字找队伍, 不区分大小写
+    pub fn find(&self, name: &str) -> Option<&Team> {
+        self.teams
+            .iter()
+            .find(|team| team.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn team_of(&self, player_id: PlayerId) -> Option<&Team> {
+        self.team(*self.members.get(&player_id)?)
+    }
+
+    pub fn members(&self, team_id: i32) -> Vec<PlayerId> {
+        let mut members: Vec<PlayerId> = self
+            .members
+            .iter()
+            .filter(|(_, team)| **team == team_id)
+            .map(|(&player_id, _)| player_id)
+            .collect();
+        members.sort();
+        members
+    }
+
+    pub fn member_count(&self, team_id: i32) -> usize {
+        self.members
+            .values()
+            .filter(|&&team| team == team_id)
+            .count()
+    }
+
+    pub fn is_full(&self, team_id: i32) -> bool {
+        self.team(team_id)
+            .and_then(|team| team.max_players)
+            .is_some_and(|max| self.member_count(team_id) >= max)
+    }
+
+    /// 加入队伍, 已经在这个队伍里的话只重新同步一次
+    pub fn join(&mut self, player_id: PlayerId, team_id: i32) -> TeamResult<()> {
+        let team = self.team(team_id).ok_or(TeamError::NoSuchTeam(team_id))?;
+        if self.members.get(&player_id) != Some(&team_id) && self.is_full(team_id) {
+            return Err(TeamError::Full(team_id));
+        }
+        Self::apply(player_id, team)?;
+        if self.members.insert(player_id, team_id) != Some(team_id) {
+            self.joined.insert(player_id, self.next_join);
+            self.next_join += 1;
+        }
+        Ok(())
+    }
+
+    /// 离开队伍, 不会改玩家的队伍号和颜色
+    pub fn leave(&mut self, player_id: PlayerId) -> Option<i32> {
+        self.joined.remove(&player_id);
+        self.members.remove(&player_id)
+    }
+
+    /// 加入人数最少且没满的队伍, 所有队伍都满了时返回 `Full(-1)`
+    pub fn auto_assign(&mut self, player_id: PlayerId) -> TeamResult<i32> {
+        let team_id = self
+            .teams
+            .iter()
+            .filter(|team| !self.is_full(team.id))
+            .min_by_key(|team| self.member_count(team.id))
+            .map(|team| team.id)
+            .ok_or(TeamError::Full(-1))?;
+        self.join(player_id, team_id)?;
+        Ok(team_id)
+    }
+
+    /// 把人从最多的队伍挪到最少的队伍, 直到人数差不超过 `max_difference` (至少为 1)
+    ///
+    /// 返回被挪动的 (玩家, 原队伍, 新队伍)
+    pub fn balance(&mut self, max_difference: usize) -> Vec<(PlayerId, i32, i32)> {
+        let mut moved = Vec::new();
+        loop {
+            let counts: Vec<(i32, usize)> = self
+                .teams
+                .iter()
+                .map(|team| (team.id, self.member_count(team.id)))
+                .collect();
+            let Some(&(largest, most)) = counts.iter().max_by_key(|(_, count)| *count) else {
+                break;
+            };
+            let Some(&(smallest, least)) = counts
+                .iter()
+                .filter(|(id, _)| !self.is_full(*id))
+                .min_by_key(|(_, count)| *count)
+            else {
+                break;
+            };
+            if most <= least + max_difference.max(1) {
+                break;
+            }
+            // 最后加入的人先走
+            let Some(player_id) = self
+                .members(largest)
+                .into_iter()
+                .max_by_key(|player_id| self.joined.get(player_id).copied().unwrap_or(0))
+            else {
+                break;
+            };
+            if self.join(player_id, smallest).is_err() {
+                break;
+            }
+            moved.push((player_id, largest, smallest));
+        }
+        moved
+    }
+
+    pub fn set_friendly_fire(&self, enabled: bool) {
+        vcmp_func().set_server_option(VcmpServerOption::FriendlyFire, enabled);
+    }
+
+    pub fn set_only_show_team_markers(&self, enabled: bool) {
+        vcmp_func().set_server_option(VcmpServerOption::OnlyShowTeamMarkers, enabled);
+    }
+
+    /// 用队伍颜色给队伍里的每个人发消息
+    pub fn broadcast(&self, team_id: i32, message: &str) {
+        let Some(team) = self.team(team_id) else {
+            return;
+        };
+        let func = vcmp_func();
+        for player_id in self.members(team_id) {
+            let _ = func.send_client_message(player_id, team.color, message);
+        }
+    }
+
+    pub fn announce(&self, team_id: i32, style: AnnounceStyle, message: &str) {
+        let func = vcmp_func();
+        for player_id in self.members(team_id) {
+            let _ = func.send_announce(player_id, style, message);
+        }
+    }
+
+    /// 队伍聊天的前缀, 默认是 `!`, None 表示关掉
+    pub fn set_chat_prefix(&mut self, prefix: Option<&str>) {
+        self.chat_prefix = prefix.map(|prefix| prefix.to_string());
+    }
+
+    /// 以前缀开头的消息只发给同队的人, 返回 true 时应该拦下这条消息
+    pub fn on_player_message(&self, event: &PlayerMessageEvent) -> bool {
+        let Some(prefix) = &self.chat_prefix else {
+            return false;
+        };
+        let Some(message) = event.message.strip_prefix(prefix.as_str()) else {
+            return false;
+        };
+        let Some(team) = self.team_of(event.player_id) else {
+            return false;
+        };
+        let name = vcmp_func().get_player_name(event.player_id);
+        self.broadcast(
+            team.id,
+            &format!("[{}] {name}: {}", team.name, message.trim()),
+        );
+        true
+    }
+
+    pub fn on_player_spawn(&self, event: &PlayerSpawnEvent) {
+        if let Some(team) = self.team_of(event.player_id) {
+            let _ = Self::apply(event.player_id, team);
+        }
+    }
+
+    pub fn on_player_disconnect(&mut self, event: &PlayerDisconnectEvent) {
+        self.leave(event.player_id);
+    }
+
+    fn apply(player_id: PlayerId, team: &Team) -> TeamResult<()> {
+        let func = vcmp_func();
+        func.set_player_team(player_id, team.id)?;
+        func.set_player_color(player_id, team.color)?;
+        Ok(())
+    }
+}
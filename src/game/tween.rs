@@ -0,0 +1,359 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::events::server::ServerFrameEvent;
+use crate::func::ObjectMethods;
+use crate::game::FrameClock;
+use crate::utils::{Quaternionf32, Vectorf32};
+use crate::{ObjectId, VcmpResult, vcmp_func};
+
+/// 动画里的一步, 时长就是传给服务器的 duration
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TweenStep {
+    MoveTo(Vectorf32, Duration),
+    MoveBy(Vectorf32, Duration),
+    RotateTo(Quaternionf32, Duration),
+    RotateToEuler(Vectorf32, Duration),
+    RotateBy(Quaternionf32, Duration),
+    RotateByEuler(Vectorf32, Duration),
+    /// 什么都不做, 停一会
+    Wait(Duration),
+}
+
+impl TweenStep {
+    pub fn duration(&self) -> Duration {
+        match *self {
+            TweenStep::MoveTo(_, duration)
+            | TweenStep::MoveBy(_, duration)
+            | TweenStep::RotateTo(_, duration)
+            | TweenStep::RotateToEuler(_, duration)
+            | TweenStep::RotateBy(_, duration)
+            | TweenStep::RotateByEuler(_, duration)
+            | TweenStep::Wait(duration) => duration,
+        }
+    }
+
+    /// 执行这一步, 返回倒着播放时用的那一步
+    fn apply(&self, object_id: ObjectId) -> VcmpResult<TweenStep> {
+        let func = vcmp_func();
+        let duration = self.duration();
+        let millis = duration.as_millis().min(u32::MAX as u128) as u32;
+        match *self {
+            TweenStep::MoveTo(position, _) | TweenStep::MoveBy(position, _) => {
+                let before = func.get_object_position(object_id)?;
+                if matches!(self, TweenStep::MoveTo(..)) {
+                    func.move_object_to(object_id, position, millis)?;
+                } else {
+                    func.move_object_by(object_id, position, millis)?;
+                }
+                Ok(TweenStep::MoveTo(before, duration))
+            }
+            TweenStep::RotateTo(rotation, _) | TweenStep::RotateBy(rotation, _) => {
+                let before = func.get_object_rotation(object_id)?;
+                if matches!(self, TweenStep::RotateTo(..)) {
+                    func.rotate_object_to(object_id, rotation, millis)?;
+                } else {
+                    func.rotate_object_by(object_id, rotation, millis)?;
+                }
+                Ok(TweenStep::RotateTo(before, duration))
+            }
+            TweenStep::RotateToEuler(rotation, _) | TweenStep::RotateByEuler(rotation, _) => {
+                let before = func.get_object_rotation(object_id)?;
+                if matches!(self, TweenStep::RotateToEuler(..)) {
+                    func.rotate_object_to_euler(object_id, rotation, millis)?;
+                } else {
+                    func.rotate_object_by_euler(object_id, rotation, millis)?;
+                }
+                Ok(TweenStep::RotateTo(before, duration))
+            }
+            TweenStep::Wait(_) => Ok(TweenStep::Wait(duration)),
+        }
+    }
+}
+
+/// 播完一遍之后怎么办
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TweenRepeat {
+    #[default]
+    Once,
+    /// 从头再来, None 表示一直循环
+    Loop(Option<u32>),
+    /// 倒着播回起点再正着播, 来回一趟算一次, None 表示一直来回
+    PingPong(Option<u32>),
+}
+
+/// 一串按顺序执行的步骤
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ObjectTween {
+    pub steps: Vec<TweenStep>,
+    pub repeat: TweenRepeat,
+}
+
+impl ObjectTween {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn step(mut self, step: TweenStep) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    pub fn move_to(self, position: Vectorf32, duration: Duration) -> Self {
+        self.step(TweenStep::MoveTo(position, duration))
+    }
+
+    pub fn move_by(self, offset: Vectorf32, duration: Duration) -> Self {
+        self.step(TweenStep::MoveBy(offset, duration))
+    }
+
+    pub fn rotate_to(self, rotation: Quaternionf32, duration: Duration) -> Self {
+        self.step(TweenStep::RotateTo(rotation, duration))
+    }
+
+    pub fn rotate_to_euler(self, rotation: Vectorf32, duration: Duration) -> Self {
+        self.step(TweenStep::RotateToEuler(rotation, duration))
+    }
+
+    pub fn wait(self, duration: Duration) -> Self {
+        self.step(TweenStep::Wait(duration))
+    }
+
+    pub fn repeat(mut self, repeat: TweenRepeat) -> Self {
+        self.repeat = repeat;
+        self
+    }
+
+    /// 正着播一遍的时长
+    pub fn duration(&self) -> Duration {
+        self.steps.iter().map(TweenStep::duration).sum()
+    }
+}
+
+/// 正在播放的动画的状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TweenStatus {
+    /// 当前步骤在 `steps` 里的下标
+    pub step: usize,
+    /// 正在倒着播放 (`PingPong` 的回程)
+    pub reversing: bool,
+    /// 已经播完的遍数 / 来回数
+    pub cycles: u32,
+    /// 当前步骤还剩多久
+    pub step_remaining: Duration,
+}
+
+type CompletionHandler = Box<dyn FnMut(ObjectId) + Send>;
+
+struct Running {
+    tween: ObjectTween,
+    on_complete: Option<CompletionHandler>,
+    /// 正着播放时每一步执行前的状态, 倒着播放时用
+    undo: Vec<Option<TweenStep>>,
+    index: usize,
+    reversing: bool,
+    cycles: u32,
+    step_end: Duration,
+}
+
+/// 物体动画
+///
+/// 服务器不会告诉我们移动什么时候结束, 所以按每一步的时长用帧时间推算
+///
+/// 物体被删掉或者服务器调用失败时动画直接丢掉, 不会调用完成回调.
+/// `stop` 也不会调用
+///
+/// 需要在 `ServerFrame` 里调用对应的方法
+#[derive(Default)]
+pub struct ObjectAnimator {
+    clock: FrameClock,
+    running: HashMap<ObjectId, Running>,
+}
+
+impl ObjectAnimator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 开始播放, 会替换这个物体正在播的动画
+    pub fn play(&mut self, object_id: ObjectId, tween: ObjectTween) -> VcmpResult<()> {
+        self.start(object_id, tween, None)
+    }
+
+    /// 和 `play` 一样, 播完时调用 `on_complete`. 一直循环的动画不会播完
+    pub fn play_with(
+        &mut self,
+        object_id: ObjectId,
+        tween: ObjectTween,
+        on_complete: impl FnMut(ObjectId) + Send + 'static,
+    ) -> VcmpResult<()> {
+        self.start(object_id, tween, Some(Box::new(on_complete)))
+    }
+
+    /// 停在当前位置, 不会调用完成回调. 没有动画时返回 false
+    pub fn stop(&mut self, object_id: ObjectId) -> bool {
+        let Some(running) = self.running.remove(&object_id) else {
+            return false;
+        };
+        // 把正在进行的移动 / 旋转停在原地
+        let func = vcmp_func();
+        let step = if running.reversing {
+            running.undo.get(running.index).copied().flatten()
+        } else {
+            running.tween.steps.get(running.index).copied()
+        };
+        match step {
+            Some(TweenStep::MoveTo(..) | TweenStep::MoveBy(..)) => {
+                if let Ok(position) = func.get_object_position(object_id) {
+                    let _ = func.move_object_to(object_id, position, 0);
+                }
+            }
+            Some(TweenStep::Wait(_)) | None => {}
+            Some(_) => {
+                if let Ok(rotation) = func.get_object_rotation(object_id) {
+                    let _ = func.rotate_object_to(object_id, rotation, 0);
+                }
+            }
+        }
+        true
+    }
+
+    pub fn stop_all(&mut self) {
+        let objects: Vec<ObjectId> = self.running.keys().copied().collect();
+        for object_id in objects {
+            self.stop(object_id);
+        }
+    }
+
+    pub fn is_animating(&self, object_id: ObjectId) -> bool {
+        self.running.contains_key(&object_id)
+    }
+
+    pub fn status(&self, object_id: ObjectId) -> Option<TweenStatus> {
+        let running = self.running.get(&object_id)?;
+        Some(TweenStatus {
+            step: running.index,
+            reversing: running.reversing,
+            cycles: running.cycles,
+            step_remaining: running.step_end.saturating_sub(self.clock.now()),
+        })
+    }
+
+    pub fn on_server_frame(&mut self, event: &ServerFrameEvent) {
+        self.clock.advance(event);
+        let now = self.clock.now();
+        let func = vcmp_func();
+        let mut finished = Vec::new();
+        let mut dropped = Vec::new();
+        for (&object_id, running) in self.running.iter_mut() {
+            if !func.is_object_alive(object_id) {
+                dropped.push(object_id);
+                continue;
+            }
+            // 全是 0 时长的步骤时一帧最多走一遍, 免得死循环
+            let mut budget = running.tween.steps.len() * 2;
+            while running.step_end <= now && budget > 0 {
+                budget -= 1;
+                match Self::advance(object_id, running) {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        finished.push(object_id);
+                        break;
+                    }
+                    Err(_) => {
+                        dropped.push(object_id);
+                        break;
+                    }
+                }
+            }
+        }
+        for object_id in dropped {
+            self.running.remove(&object_id);
+        }
+        for object_id in finished {
+            if let Some(mut running) = self.running.remove(&object_id)
+                && let Some(handler) = running.on_complete.as_mut()
+            {
+                handler(object_id);
+            }
+        }
+    }
+
+    fn start(
+        &mut self,
+        object_id: ObjectId,
+        tween: ObjectTween,
+        on_complete: Option<CompletionHandler>,
+    ) -> VcmpResult<()> {
+        self.stop(object_id);
+        let Some(first) = tween.steps.first().copied() else {
+            // 没有步骤的动画直接算播完
+            if let Some(mut handler) = on_complete {
+                handler(object_id);
+            }
+            return Ok(());
+        };
+        let undo = first.apply(object_id)?;
+        let mut running = Running {
+            undo: vec![None; tween.steps.len()],
+            tween,
+            on_complete,
+            index: 0,
+            reversing: false,
+            cycles: 0,
+            step_end: self.clock.now() + first.duration(),
+        };
+        running.undo[0] = Some(undo);
+        self.running.insert(object_id, running);
+        Ok(())
+    }
+
+    /// 开始下一步, 整个动画播完时返回 false
+    fn advance(object_id: ObjectId, running: &mut Running) -> VcmpResult<bool> {
+        let last = running.tween.steps.len() - 1;
+        if !running.reversing {
+            if running.index < last {
+                running.index += 1;
+            } else {
+                match running.tween.repeat {
+                    TweenRepeat::Once => return Ok(false),
+                    TweenRepeat::Loop(times) => {
+                        running.cycles += 1;
+                        if times.is_some_and(|times| running.cycles >= times) {
+                            return Ok(false);
+                        }
+                        running.index = 0;
+                    }
+                    TweenRepeat::PingPong(_) => running.reversing = true,
+                }
+            }
+        } else if running.index > 0 {
+            running.index -= 1;
+        } else {
+            running.cycles += 1;
+            if let TweenRepeat::PingPong(Some(times)) = running.tween.repeat
+                && running.cycles >= times
+            {
+                return Ok(false);
+            }
+            running.reversing = false;
+        }
+
+        let index = running.index;
+        let duration = if running.reversing {
+            match running.undo[index] {
+                Some(step) => {
+                    step.apply(object_id)?;
+                    step.duration()
+                }
+                None => Duration::ZERO,
+            }
+        } else {
+            let step = running.tween.steps[index];
+            running.undo[index] = Some(step.apply(object_id)?);
+            step.duration()
+        };
+        running.step_end += duration;
+        Ok(true)
+    }
+}
@@ -0,0 +1,175 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::RangeInclusive;
+
+use crate::events::player::PlayerDisconnectEvent;
+use crate::func::PlayerMethods;
+use crate::game::GameEntity;
+use crate::types::WorldId;
+use crate::{PlayerId, VcmpResult, vcmp_func};
+
+/// 一个分配出去的世界
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorldInstance {
+    pub world: WorldId,
+    pub name: String,
+    pub players: BTreeSet<PlayerId>,
+    pub entities: Vec<GameEntity>,
+}
+
+/// 分配互不冲突的世界号
+///
+/// 每个实例记录了里面的玩家和实体. 释放实例时玩家会被送回默认世界, 实体会被删掉
+///
+/// 需要在 `PlayerDisconnect` 里调用对应的方法
+#[derive(Debug, Clone)]
+pub struct WorldAllocator {
+    range: RangeInclusive<WorldId>,
+    default_world: WorldId,
+    instances: BTreeMap<WorldId, WorldInstance>,
+}
+
+impl Default for WorldAllocator {
+    fn default() -> Self {
+        Self::new(1000..=9999, 1)
+    }
+}
+
+impl WorldAllocator {
+    /// 只会在 `range` 里分配世界号, 释放之后玩家回到 `default_world`
+    pub fn new(range: RangeInclusive<WorldId>, default_world: WorldId) -> Self {
+        Self {
+            range,
+            default_world,
+            instances: BTreeMap::new(),
+        }
+    }
+
+    pub fn default_world(&self) -> WorldId {
+        self.default_world
+    }
+
+    pub fn set_default_world(&mut self, world: WorldId) {
+        self.default_world = world;
+    }
+
+    /// 分配最小的空闲世界号, 用完了返回 None
+    pub fn allocate(&mut self, name: &str) -> Option<WorldId> {
+        let world = self
+            .range
+            .clone()
+            .find(|world| !self.instances.contains_key(world))?;
+        self.instances.insert(
+            world,
+            WorldInstance {
+                world,
+                name: name.to_string(),
+                players: BTreeSet::new(),
+                entities: Vec::new(),
+            },
+        );
+        Some(world)
+    }
+
+    /// 把玩家送回默认世界, 删掉所有实体. 不存在的实例返回 None
+    pub fn release(&mut self, world: WorldId) -> Option<WorldInstance> {
+        let instance = self.instances.remove(&world)?;
+        let func = vcmp_func();
+        for &player_id in &instance.players {
+            let _ = func.set_player_world(player_id, self.default_world);
+        }
+        for entity in &instance.entities {
+            let _ = entity.destroy();
+        }
+        Some(instance)
+    }
+
+    pub fn release_all(&mut self) {
+        let worlds: Vec<WorldId> = self.instances.keys().copied().collect();
+        for world in worlds {
+            self.release(world);
+        }
+    }
+
+    pub fn is_allocated(&self, world: WorldId) -> bool {
+        self.instances.contains_key(&world)
+    }
+
+    pub fn instance(&self, world: WorldId) -> Option<&WorldInstance> {
+        self.instances.get(&world)
+    }
+
+    pub fn instances(&self) -> impl Iterator<Item = &WorldInstance> {
+        self.instances.values()
+    }
+
+    /// 玩家所在的实例
+    pub fn instance_of(&self, player_id: PlayerId) -> Option<&WorldInstance> {
+        self.instances
+            .values()
+            .find(|instance| instance.players.contains(&player_id))
+    }
+
+    /// 把玩家放进实例, 会离开之前所在的实例
+    pub fn add_player(&mut self, world: WorldId, player_id: PlayerId) -> VcmpResult<bool> {
+        if !self.instances.contains_key(&world) {
+            return Ok(false);
+        }
+        vcmp_func().set_player_world(player_id, world)?;
+        self.forget_player(player_id);
+        if let Some(instance) = self.instances.get_mut(&world) {
+            instance.players.insert(player_id);
+        }
+        Ok(true)
+    }
+
+    /// 把玩家送回默认世界, 不在任何实例里时返回 false
+    pub fn remove_player(&mut self, player_id: PlayerId) -> VcmpResult<bool> {
+        if !self.forget_player(player_id) {
+            return Ok(false);
+        }
+        vcmp_func().set_player_world(player_id, self.default_world)?;
+        Ok(true)
+    }
+
+    /// 记录一个已经在这个世界里创建好的实体
+    pub fn track(&mut self, world: WorldId, entity: GameEntity) -> bool {
+        let Some(instance) = self.instances.get_mut(&world) else {
+            return false;
+        };
+        if !instance.entities.contains(&entity) {
+            instance.entities.push(entity);
+        }
+        true
+    }
+
+    /// 把实体挪到这个世界并记录下来, 小地图图标没法挪, 请创建时直接用 `track`
+    pub fn move_entity(&mut self, world: WorldId, entity: GameEntity) -> VcmpResult<bool> {
+        if !self.instances.contains_key(&world) {
+            return Ok(false);
+        }
+        entity.set_world(world)?;
+        self.untrack(entity);
+        Ok(self.track(world, entity))
+    }
+
+    /// 不再记录这个实体, 不会删掉它
+    pub fn untrack(&mut self, entity: GameEntity) -> bool {
+        let mut found = false;
+        for instance in self.instances.values_mut() {
+            let before = instance.entities.len();
+            instance.entities.retain(|tracked| *tracked != entity);
+            found |= instance.entities.len() != before;
+        }
+        found
+    }
+
+    pub fn on_player_disconnect(&mut self, event: &PlayerDisconnectEvent) {
+        self.forget_player(event.player_id);
+    }
+
+    fn forget_player(&mut self, player_id: PlayerId) -> bool {
+        self.instances
+            .values_mut()
+            .any(|instance| instance.players.remove(&player_id))
+    }
+}
@@ -4,6 +4,12 @@
 #[allow(non_camel_case_types, non_snake_case, non_upper_case_globals)]
 pub mod raw;
 
+/// 插件间的消息总线
+pub mod bus;
+/// 游戏内常量的枚举
+pub mod catalog;
+/// 用 ServerFrame 累加出来的时钟, 各层定时用
+pub mod clock;
 /// gbk <-> utf8
 pub mod encodes;
 /// vcmp error & vcmp result
@@ -16,10 +22,16 @@ pub mod events;
 ///
 /// 帮你解决好各种 call 问题
 pub mod func;
+/// func 之上的玩法封装
+pub mod game;
+/// 服务器管理用的封装
+pub mod moderation;
 /// wrapper for option enums
 pub mod options;
 /// wrapper for PluginInfo
 pub mod plugin_info;
+/// 客户端脚本通信
+pub mod script;
 /// wrapper for PluginSetting
 pub mod setting;
 pub mod states;
@@ -0,0 +1,17 @@
+//! 服务器管理用的一些封装
+//!
+//! 和 game 一样, 这些东西都不会自己注册回调, 需要在对应的事件里手动调用 `on_xxx`
+
+pub mod ban;
+pub mod ip_range;
+pub mod network;
+pub mod performance;
+
+pub use crate::types::{IpRange, IpRangeError};
+pub use ban::{BanEntry, BanList};
+pub use ip_range::{IpBan, IpBanTable};
+pub use network::{
+    NetworkAlert, NetworkMetric, NetworkMonitor, NetworkReport, NetworkSample, NetworkThreshold,
+    ThresholdAction,
+};
+pub use performance::{PerformanceLimits, PerformancePolicy, PerformanceViolation, ViolationKind};
@@ -0,0 +1,526 @@
+use std::fmt::Display;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::events::player::{IncomingConnectionEvent, PlayerConnectEvent};
+use crate::func::PlayerMethods;
+use crate::{PlayerId, vcmp_func};
+
+/// 现在的 unix 时间 (秒)
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+/// 一条封禁
+///
+/// name / ip / uid / uid2 里任意一个对上了都算被封
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BanEntry {
+    /// 由 `BanList::add` 分配
+    pub id: u64,
+    pub name: Option<String>,
+    pub ip: Option<String>,
+    pub uid: Option<String>,
+    pub uid2: Option<String>,
+    pub reason: String,
+    pub issuer: String,
+    /// unix 时间 (秒)
+    pub created_at: u64,
+    /// unix 时间 (秒), None 表示永久
+    pub expires_at: Option<u64>,
+}
+
+impl BanEntry {
+    pub fn new(reason: &str, issuer: &str) -> Self {
+        Self {
+            id: 0,
+            name: None,
+            ip: None,
+            uid: None,
+            uid2: None,
+            reason: reason.to_string(),
+            issuer: issuer.to_string(),
+            created_at: unix_now(),
+            expires_at: None,
+        }
+    }
+
+    /// 用在线玩家的名字 / ip / uid / uid2 填好
+    pub fn for_player(player_id: PlayerId, reason: &str, issuer: &str) -> Self {
+        let func = vcmp_func();
+        Self::new(reason, issuer)
+            .name(&func.get_player_name(player_id))
+            .ip(&func.get_player_ip(player_id))
+            .uid(&func.get_player_uid(player_id))
+            .uid2(&func.get_player_uid2(player_id))
+    }
+
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = non_empty(name);
+        self
+    }
+
+    pub fn ip(mut self, ip: &str) -> Self {
+        self.ip = non_empty(ip);
+        self
+    }
+
+    pub fn uid(mut self, uid: &str) -> Self {
+        self.uid = non_empty(uid);
+        self
+    }
+
+    pub fn uid2(mut self, uid2: &str) -> Self {
+        self.uid2 = non_empty(uid2);
+        self
+    }
+
+    /// 从创建时间开始算的时长
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.expires_at = Some(self.created_at.saturating_add(duration.as_secs()));
+        self
+    }
+
+    pub fn is_permanent(&self) -> bool {
+        self.expires_at.is_none()
+    }
+
+    pub fn is_expired_at(&self, now: u64) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.is_expired_at(unix_now())
+    }
+
+    /// 剩余时间, 永久封禁返回 None
+    pub fn remaining(&self) -> Option<Duration> {
+        self.expires_at
+            .map(|expires_at| Duration::from_secs(expires_at.saturating_sub(unix_now())))
+    }
+
+    /// 名字不区分大小写, 其他的要完全一致
+    pub fn matches(
+        &self,
+        name: Option<&str>,
+        ip: Option<&str>,
+        uid: Option<&str>,
+        uid2: Option<&str>,
+    ) -> bool {
+        let same = |field: &Option<String>, value: Option<&str>| {
+            field.as_deref().zip(value).is_some_and(|(a, b)| a == b)
+        };
+        let name_matches = self
+            .name
+            .as_deref()
+            .zip(name)
+            .is_some_and(|(a, b)| a.eq_ignore_ascii_case(b));
+        name_matches || same(&self.ip, ip) || same(&self.uid, uid) || same(&self.uid2, uid2)
+    }
+
+    fn fields(&self) -> [Option<&str>; 6] {
+        [
+            self.name.as_deref(),
+            self.ip.as_deref(),
+            self.uid.as_deref(),
+            self.uid2.as_deref(),
+            Some(&self.reason),
+            Some(&self.issuer),
+        ]
+    }
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+fn escape(value: Option<&str>) -> String {
+    match value {
+        None => "-".to_string(),
+        Some(value) => {
+            let mut escaped = String::with_capacity(value.len());
+            for c in value.chars() {
+                match c {
+                    '\\' => escaped.push_str("\\\\"),
+                    '\t' => escaped.push_str("\\t"),
+                    '\n' => escaped.push_str("\\n"),
+                    '\r' => escaped.push_str("\\r"),
+                    '-' if value == "-" => escaped.push_str("\\-"),
+                    c => escaped.push(c),
+                }
+            }
+            escaped
+        }
+    }
+}
+
+fn unescape(value: &str) -> Option<String> {
+    if value == "-" {
+        return None;
+    }
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some(other) => unescaped.push(other),
+            None => unescaped.push('\\'),
+        }
+    }
+    Some(unescaped)
+}
+
+/// 文件里一行一条, 用 tab 分隔:
+///
+/// `id created_at expires_at name ip uid uid2 issuer reason`
+///
+/// 空的字段写成 `-`
+impl Display for BanEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let expires_at = self
+            .expires_at
+            .map(|expires_at| expires_at.to_string())
+            .unwrap_or_else(|| "-".to_string());
+        write!(
+            f,
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            self.id,
+            self.created_at,
+            expires_at,
+            escape(self.name.as_deref()),
+            escape(self.ip.as_deref()),
+            escape(self.uid.as_deref()),
+            escape(self.uid2.as_deref()),
+            escape(Some(&self.issuer)),
+            escape(Some(&self.reason)),
+        )
+    }
+}
+
+/// 存在本地文件里的封禁列表
+///
+/// 和服务器自带的封禁列表无关. 设置了路径的时候每次修改都会立刻写回文件
+///
+/// 需要在 `IncomingConnection` 和 `PlayerConnect` 里调用对应的方法,
+/// 因为 uid 要连上之后才拿得到
+#[derive(Debug)]
+pub struct BanList {
+    path: Option<PathBuf>,
+    entries: Vec<BanEntry>,
+    next_id: u64,
+}
+
+impl Default for BanList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BanList {
+    /// 只在内存里的列表
+    pub fn new() -> Self {
+        Self {
+            path: None,
+            entries: Vec::new(),
+            next_id: 1,
+        }
+    }
+
+    /// 从文件加载, 文件不存在时是空列表
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let mut list = match std::fs::read_to_string(path) {
+            Ok(content) => Self::parse(&content)?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Self::new(),
+            Err(err) => return Err(err),
+        };
+        list.path = Some(path.to_path_buf());
+        Ok(list)
+    }
+
+    pub fn parse(content: &str) -> io::Result<Self> {
+        let mut list = Self::new();
+        for (index, line) in content.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = |reason: &str| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {}: {reason}: {line}", index + 1),
+                )
+            };
+            let parts: Vec<&str> = line.split('\t').collect();
+            if parts.len() != 9 {
+                return Err(invalid("expected 9 fields"));
+            }
+            let number = |value: &str| value.parse::<u64>().map_err(|_| invalid("invalid number"));
+            let entry = BanEntry {
+                id: number(parts[0])?,
+                created_at: number(parts[1])?,
+                expires_at: match parts[2] {
+                    "-" => None,
+                    value => Some(number(value)?),
+                },
+                name: unescape(parts[3]),
+                ip: unescape(parts[4]),
+                uid: unescape(parts[5]),
+                uid2: unescape(parts[6]),
+                issuer: unescape(parts[7]).unwrap_or_default(),
+                reason: unescape(parts[8]).unwrap_or_default(),
+            };
+            list.next_id = list.next_id.max(entry.id + 1);
+            list.entries.push(entry);
+        }
+        Ok(list)
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        match &self.path {
+            Some(path) => self.save_to(path),
+            None => Ok(()),
+        }
+    }
+
+    pub fn save_to(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut content =
+            String::from("# id\tcreated_at\texpires_at\tname\tip\tuid\tuid2\tissuer\treason\n");
+        for entry in &self.entries {
+            content.push_str(&entry.to_string());
+            content.push('\n');
+        }
+        std::fs::write(path, content)
+    }
+
+    /// 加一条封禁, 返回分配的 id
+    pub fn add(&mut self, mut entry: BanEntry) -> io::Result<u64> {
+        entry.id = self.next_id;
+        self.next_id += 1;
+        let id = entry.id;
+        self.entries.push(entry);
+        self.save()?;
+        Ok(id)
+    }
+
+    /// 封禁在线玩家并踢出
+    pub fn ban_player(
+        &mut self,
+        player_id: PlayerId,
+        reason: &str,
+        issuer: &str,
+        duration: Option<Duration>,
+    ) -> io::Result<u64> {
+        let mut entry = BanEntry::for_player(player_id, reason, issuer);
+        if let Some(duration) = duration {
+            entry = entry.duration(duration);
+        }
+        let id = self.add(entry)?;
+        vcmp_func().kick_player(player_id);
+        Ok(id)
+    }
+
+    /// 解封, 返回被删掉的那条
+    pub fn unban(&mut self, id: u64) -> io::Result<Option<BanEntry>> {
+        let Some(index) = self.entries.iter().position(|entry| entry.id == id) else {
+            return Ok(None);
+        };
+        let entry = self.entries.remove(index);
+        self.save()?;
+        Ok(Some(entry))
+    }
+
+    /// 解封所有 name / ip / uid / uid2 等于 value 的, 返回解封的数量
+    pub fn unban_matching(&mut self, value: &str) -> io::Result<usize> {
+        let before = self.entries.len();
+        self.entries
+            .retain(|entry| !entry.matches(Some(value), Some(value), Some(value), Some(value)));
+        let removed = before - self.entries.len();
+        if removed > 0 {
+            self.save()?;
+        }
+        Ok(removed)
+    }
+
+    /// 删掉所有过期的, 返回删掉的数量
+    pub fn purge_expired(&mut self) -> io::Result<usize> {
+        let now = unix_now();
+        let before = self.entries.len();
+        self.entries.retain(|entry| !entry.is_expired_at(now));
+        let removed = before - self.entries.len();
+        if removed > 0 {
+            self.save()?;
+        }
+        Ok(removed)
+    }
+
+    pub fn get(&self, id: u64) -> Option<&BanEntry> {
+        self.entries.iter().find(|entry| entry.id == id)
+    }
+
+    /// 所有还没过期的封禁
+    pub fn list(&self) -> Vec<&BanEntry> {
+        let now = unix_now();
+        self.entries
+            .iter()
+            .filter(|entry| !entry.is_expired_at(now))
+            .collect()
+    }
+
+    /// 在 name / ip / uid / uid2 / 原因 / 执行人里搜索, 不区分大小写
+    pub fn search(&self, query: &str) -> Vec<&BanEntry> {
+        let query = query.to_lowercase();
+        self.entries
+            .iter()
+            .filter(|entry| {
+                entry
+                    .fields()
+                    .into_iter()
+                    .flatten()
+                    .any(|field| field.to_lowercase().contains(&query))
+            })
+            .collect()
+    }
+
+    /// 找到一条还没过期的匹配的封禁
+    pub fn find(
+        &self,
+        name: Option<&str>,
+        ip: Option<&str>,
+        uid: Option<&str>,
+        uid2: Option<&str>,
+    ) -> Option<&BanEntry> {
+        let now = unix_now();
+        self.entries
+            .iter()
+            .find(|entry| !entry.is_expired_at(now) && entry.matches(name, ip, uid, uid2))
+    }
+
+    /// 按名字和 ip 检查, 返回 Some 时应该拒绝这个连接
+    pub fn on_incoming_connection(&self, event: &IncomingConnectionEvent) -> Option<&BanEntry> {
+        self.find(Some(&event.player_name), Some(&event.ip), None, None)
+    }
+
+    /// 连上之后再按 uid / uid2 检查一遍, 被封的话直接踢出
+    pub fn on_player_connect(&self, event: &PlayerConnectEvent) -> Option<&BanEntry> {
+        let func = vcmp_func();
+        let player_id = event.player_id;
+        let entry = self.find(
+            Some(&func.get_player_name(player_id)),
+            Some(&func.get_player_ip(player_id)),
+            Some(&func.get_player_uid(player_id)),
+            Some(&func.get_player_uid2(player_id)),
+        )?;
+        func.kick_player(player_id);
+        Some(entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(reason: &str) -> BanEntry {
+        BanEntry {
+            id: 7,
+            name: Some("Tommy".to_string()),
+            ip: Some("1.2.3.4".to_string()),
+            uid: None,
+            uid2: Some("abc".to_string()),
+            reason: reason.to_string(),
+            issuer: "admin".to_string(),
+            created_at: 1000,
+            expires_at: Some(2000),
+        }
+    }
+
+    #[test]
+    fn escape_round_trip() {
+        for value in [
+            "plain",
+            "-",
+            "--",
+            "\\-",
+            "a\tb",
+            "line\nbreak\r",
+            "back\\slash\\",
+            "",
+        ] {
+            assert_eq!(unescape(&escape(Some(value))).as_deref(), Some(value));
+        }
+        assert_eq!(escape(None), "-");
+        assert_eq!(unescape("-"), None);
+        assert!(!escape(Some("a\tb\nc")).contains(['\t', '\n']));
+    }
+
+    #[test]
+    fn file_round_trip() {
+        let mut odd = entry("aimbot\tand\nwallhack \\o/");
+        odd.issuer = "-".to_string();
+        odd.expires_at = None;
+        let content = format!("# 注释\n\n{}\n{}\n", entry("spam"), odd);
+        let list = BanList::parse(&content).unwrap();
+        assert_eq!(list.entries, vec![entry("spam"), odd]);
+        assert_eq!(list.next_id, 8);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let error = BanList::parse("1\t2\t3").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().starts_with("line 1: expected 9 fields"));
+        let line = entry("x").to_string().replacen("1000", "soon", 1);
+        let error = BanList::parse(&format!("\n{line}")).unwrap_err();
+        assert!(error.to_string().starts_with("line 2: invalid number"));
+    }
+
+    #[test]
+    fn matching_and_expiry() {
+        let ban = entry("x");
+        assert!(ban.matches(Some("TOMMY"), None, None, None));
+        assert!(ban.matches(None, Some("1.2.3.4"), None, None));
+        assert!(ban.matches(None, None, None, Some("abc")));
+        assert!(!ban.matches(None, None, None, Some("ABC")));
+        // 没记录 uid 的封禁不会因为 uid 对上
+        assert!(!ban.matches(Some("Lance"), Some("1.2.3.5"), Some("abc"), None));
+        assert!(!ban.is_expired_at(1999));
+        assert!(ban.is_expired_at(2000));
+        assert!(!BanEntry::new("x", "y").is_expired_at(u64::MAX));
+
+        let timed = BanEntry::new("x", "y").duration(Duration::from_secs(60));
+        assert_eq!(timed.expires_at, Some(timed.created_at + 60));
+    }
+
+    #[test]
+    fn list_operations() {
+        let mut list = BanList::new();
+        let first = list.add(entry("spam")).unwrap();
+        let second = list
+            .add(BanEntry::new("griefing", "mod").name("Lance"))
+            .unwrap();
+        assert_eq!((first, second), (1, 2));
+        assert_eq!(list.search("GRIEF").len(), 1);
+        assert_eq!(list.search("mod").len(), 1);
+        assert!(list.find(Some("lance"), None, None, None).is_some());
+        // 第一条已经过期了
+        assert!(list.find(Some("tommy"), None, None, None).is_none());
+        assert_eq!(list.list().len(), 1);
+        assert_eq!(list.purge_expired().unwrap(), 1);
+        assert_eq!(list.unban_matching("Lance").unwrap(), 1);
+        assert!(list.unban(second).unwrap().is_none());
+    }
+}
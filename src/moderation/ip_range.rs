@@ -0,0 +1,156 @@
+use std::fmt::Display;
+use std::io;
+use std::net::IpAddr;
+use std::path::Path;
+
+use crate::events::player::IncomingConnectionEvent;
+use crate::func::AdministrationMethods;
+use crate::types::IpRange;
+use crate::vcmp_func;
+
+/// 一条 ip 段封禁
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IpBan {
+    pub range: IpRange,
+    pub reason: String,
+}
+
+/// ip 段的封禁表
+///
+/// 服务器自带的封禁列表只认单个地址和通配符, 这里可以封 CIDR 和 IPv6 段.
+/// 需要在 `IncomingConnection` 里调用 `on_incoming_connection`
+///
+/// 文件格式一行一条: `range reason`, `#` 开头的是注释
+#[derive(Debug, Clone, Default)]
+pub struct IpBanTable {
+    bans: Vec<IpBan>,
+}
+
+impl IpBanTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn parse(content: &str) -> io::Result<Self> {
+        let mut table = Self::new();
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (range, reason) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let range = range.parse::<IpRange>().map_err(|err| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {}: {err}: {line}", index + 1),
+                )
+            })?;
+            table.add(range, reason.trim());
+        }
+        Ok(table)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, self.to_string())
+    }
+
+    /// 同一个段已经封过的时候只更新原因
+    pub fn add(&mut self, range: IpRange, reason: &str) {
+        match self.bans.iter_mut().find(|ban| ban.range == range) {
+            Some(ban) => ban.reason = reason.to_string(),
+            None => self.bans.push(IpBan {
+                range,
+                reason: reason.to_string(),
+            }),
+        }
+    }
+
+    pub fn remove(&mut self, range: &IpRange) -> bool {
+        let before = self.bans.len();
+        self.bans.retain(|ban| ban.range != *range);
+        self.bans.len() != before
+    }
+
+    pub fn bans(&self) -> &[IpBan] {
+        &self.bans
+    }
+
+    pub fn len(&self) -> usize {
+        self.bans.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bans.is_empty()
+    }
+
+    /// 第一条包含这个地址的封禁
+    pub fn find(&self, addr: IpAddr) -> Option<&IpBan> {
+        self.bans.iter().find(|ban| ban.range.contains(addr))
+    }
+
+    pub fn find_str(&self, addr: &str) -> Option<&IpBan> {
+        self.find(addr.trim().parse().ok()?)
+    }
+
+    /// 返回 Some 时应该拒绝这个连接
+    pub fn on_incoming_connection(&self, event: &IncomingConnectionEvent) -> Option<&IpBan> {
+        self.find_str(&event.ip)
+    }
+
+    /// 把服务器能直接处理的条目 (单个地址和 IPv4 通配符) 加进服务器自带的封禁列表,
+    /// 返回加进去的数量
+    pub fn sync_native(&self) -> usize {
+        let func = vcmp_func();
+        self.bans
+            .iter()
+            .filter_map(|ban| ban.range.native_form())
+            .filter(|ip| func.ban_ip(ip).is_ok())
+            .count()
+    }
+}
+
+impl Display for IpBanTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for ban in &self.bans {
+            if ban.reason.is_empty() {
+                writeln!(f, "{}", ban.range)?;
+            } else {
+                writeln!(f, "{} {}", ban.range, ban.reason)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_range_in_another_notation_is_one_entry() {
+        let mut table = IpBanTable::parse("1.2.3.* cheating\n# 注释\n\n10.0.0.0/8\n").unwrap();
+        table.add("1.2.3.0/24".parse().unwrap(), "aimbot");
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.bans()[0].reason, "aimbot");
+        assert_eq!(table.to_string(), "1.2.3.* aimbot\n10.0.0.0/8\n");
+
+        assert!(table.remove(&"1.2.3.0/24".parse().unwrap()));
+        assert!(!table.remove(&"1.2.3.*".parse().unwrap()));
+        assert_eq!(
+            table.find_str("10.9.8.7").map(|ban| ban.range.prefix()),
+            Some(8)
+        );
+        assert!(table.find_str("1.2.3.4").is_none());
+    }
+
+    #[test]
+    fn parse_reports_the_line() {
+        let error = IpBanTable::parse("1.2.3.4\n1.2.3.999 nope").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().starts_with("line 2: "));
+    }
+}
@@ -0,0 +1,434 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+use crate::events::player::PlayerDisconnectEvent;
+use crate::events::server::ServerFrameEvent;
+use crate::func::{NetworkStats, PlayerMethods, QueryNetworkStatistics};
+use crate::clock::FrameClock;
+use crate::{PlayerId, vcmp_func};
+
+/// 一次采样
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NetworkSample {
+    /// 采样时 `FrameClock` 的时间
+    pub at: Duration,
+    pub ping: i32,
+    pub stats: NetworkStats,
+}
+
+/// 根据窗口里的采样算出来的数据
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct NetworkReport {
+    pub samples: usize,
+    pub average_ping: f64,
+    pub max_ping: i32,
+    /// ping 的变化趋势, 毫秒每秒, 正数表示越来越卡
+    pub ping_trend: f64,
+    /// 平均丢包率, 0 ~ 1
+    pub packet_loss: f64,
+    /// 平均重发比例, 0 ~ 1
+    pub resend_rate: f64,
+    pub messages_waiting: f64,
+}
+
+impl NetworkReport {
+    pub fn metric(&self, metric: NetworkMetric) -> f64 {
+        match metric {
+            NetworkMetric::Ping => self.average_ping,
+            NetworkMetric::PingTrend => self.ping_trend,
+            NetworkMetric::PacketLoss => self.packet_loss,
+            NetworkMetric::ResendRate => self.resend_rate,
+            NetworkMetric::MessagesWaiting => self.messages_waiting,
+        }
+    }
+
+    fn from_samples(samples: &VecDeque<NetworkSample>) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        let count = samples.len() as f64;
+        let average = |f: fn(&NetworkSample) -> f64| samples.iter().map(f).sum::<f64>() / count;
+        Self {
+            samples: samples.len(),
+            average_ping: average(|sample| sample.ping as f64),
+            max_ping: samples.iter().map(|sample| sample.ping).max().unwrap_or(0),
+            ping_trend: ping_slope(samples),
+            packet_loss: average(|sample| sample.stats.packet_loss_per_second),
+            resend_rate: average(|sample| sample.stats.resend_ratio()),
+            messages_waiting: average(|sample| sample.stats.messages_waiting),
+        }
+    }
+}
+
+/// 最小二乘拟合出来的 ping 斜率
+fn ping_slope(samples: &VecDeque<NetworkSample>) -> f64 {
+    if samples.len() < 2 {
+        return 0.0;
+    }
+    let count = samples.len() as f64;
+    let mean_t = samples.iter().map(|s| s.at.as_secs_f64()).sum::<f64>() / count;
+    let mean_p = samples.iter().map(|s| s.ping as f64).sum::<f64>() / count;
+    let (mut numerator, mut denominator) = (0.0, 0.0);
+    for sample in samples {
+        let dt = sample.at.as_secs_f64() - mean_t;
+        numerator += dt * (sample.ping as f64 - mean_p);
+        denominator += dt * dt;
+    }
+    if denominator > 0.0 {
+        numerator / denominator
+    } else {
+        0.0
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum NetworkMetric {
+    Ping,
+    PingTrend,
+    PacketLoss,
+    ResendRate,
+    MessagesWaiting,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum ThresholdAction {
+    /// 只触发回调
+    #[default]
+    Notify,
+    /// 触发回调之后踢出玩家
+    Kick,
+}
+
+/// 某个指标持续超过限制时触发
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetworkThreshold {
+    pub name: String,
+    pub metric: NetworkMetric,
+    pub limit: f64,
+    /// 连续多少次采样超过限制才触发
+    pub sustained: u32,
+    pub action: ThresholdAction,
+}
+
+impl NetworkThreshold {
+    pub fn new(name: &str, metric: NetworkMetric, limit: f64) -> Self {
+        Self {
+            name: name.to_string(),
+            metric,
+            limit,
+            sustained: 1,
+            action: ThresholdAction::Notify,
+        }
+    }
+
+    pub fn sustained(mut self, samples: u32) -> Self {
+        self.sustained = samples.max(1);
+        self
+    }
+
+    pub fn kick(mut self) -> Self {
+        self.action = ThresholdAction::Kick;
+        self
+    }
+}
+
+/// 阈值触发时交给回调的信息
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetworkAlert {
+    pub player_id: PlayerId,
+    pub threshold: String,
+    pub metric: NetworkMetric,
+    pub value: f64,
+    pub limit: f64,
+    pub action: ThresholdAction,
+}
+
+type AlertCallback = Box<dyn FnMut(&NetworkAlert) + Send>;
+
+#[derive(Debug, Default)]
+struct PlayerNetwork {
+    samples: VecDeque<NetworkSample>,
+    /// 每个阈值连续超过的次数
+    streaks: Vec<u32>,
+}
+
+/// 定时采样每个玩家的网络状况
+///
+/// 每个玩家保留最近 `window` 次采样, 每次采样之后检查一遍阈值.
+/// 同一个阈值持续超过时只会触发一次, 恢复之后才会再次触发
+///
+/// 需要在 `ServerFrame` 和 `PlayerDisconnect` 里调用对应的方法
+pub struct NetworkMonitor {
+    clock: FrameClock,
+    interval: Duration,
+    next_sample: Duration,
+    window: usize,
+    thresholds: Vec<NetworkThreshold>,
+    players: HashMap<PlayerId, PlayerNetwork>,
+    on_alert: Option<AlertCallback>,
+}
+
+impl Default for NetworkMonitor {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(5), 12)
+    }
+}
+
+impl NetworkMonitor {
+    pub fn new(interval: Duration, window: usize) -> Self {
+        Self {
+            clock: FrameClock::new(),
+            interval,
+            next_sample: interval,
+            window: window.max(1),
+            thresholds: Vec::new(),
+            players: HashMap::new(),
+            on_alert: None,
+        }
+    }
+
+    pub fn set_interval(&mut self, interval: Duration) {
+        self.interval = interval;
+        self.next_sample = self.clock.now() + interval;
+    }
+
+    pub fn set_window(&mut self, window: usize) {
+        self.window = window.max(1);
+        for player in self.players.values_mut() {
+            while player.samples.len() > self.window {
+                player.samples.pop_front();
+            }
+        }
+    }
+
+    pub fn add_threshold(&mut self, threshold: NetworkThreshold) {
+        self.thresholds.push(threshold);
+        for player in self.players.values_mut() {
+            player.streaks.resize(self.thresholds.len(), 0);
+        }
+    }
+
+    pub fn clear_thresholds(&mut self) {
+        self.thresholds.clear();
+        for player in self.players.values_mut() {
+            player.streaks.clear();
+        }
+    }
+
+    pub fn on_alert(&mut self, callback: impl FnMut(&NetworkAlert) + Send + 'static) {
+        self.on_alert = Some(Box::new(callback));
+    }
+
+    /// 窗口里的采样, 旧的在前
+    pub fn samples(&self, player_id: PlayerId) -> Option<&VecDeque<NetworkSample>> {
+        self.players.get(&player_id).map(|player| &player.samples)
+    }
+
+    pub fn latest(&self, player_id: PlayerId) -> Option<&NetworkSample> {
+        self.players.get(&player_id)?.samples.back()
+    }
+
+    pub fn report(&self, player_id: PlayerId) -> Option<NetworkReport> {
+        let player = self.players.get(&player_id)?;
+        Some(NetworkReport::from_samples(&player.samples))
+    }
+
+    /// 所有有采样的玩家的报告
+    pub fn reports(&self) -> Vec<(PlayerId, NetworkReport)> {
+        let mut reports: Vec<_> = self
+            .players
+            .iter()
+            .map(|(&id, player)| (id, NetworkReport::from_samples(&player.samples)))
+            .collect();
+        reports.sort_by_key(|(id, _)| *id);
+        reports
+    }
+
+    /// 手动加一次采样并检查阈值, 返回这次触发的警报
+    pub fn record(
+        &mut self,
+        player_id: PlayerId,
+        ping: i32,
+        stats: NetworkStats,
+    ) -> Vec<NetworkAlert> {
+        let threshold_count = self.thresholds.len();
+        let player = self.players.entry(player_id).or_default();
+        player.streaks.resize(threshold_count, 0);
+        player.samples.push_back(NetworkSample {
+            at: self.clock.now(),
+            ping,
+            stats,
+        });
+        while player.samples.len() > self.window {
+            player.samples.pop_front();
+        }
+
+        let report = NetworkReport::from_samples(&player.samples);
+        let mut alerts = Vec::new();
+        for (threshold, streak) in self.thresholds.iter().zip(player.streaks.iter_mut()) {
+            let value = report.metric(threshold.metric);
+            if value <= threshold.limit {
+                *streak = 0;
+                continue;
+            }
+            *streak += 1;
+            if *streak == threshold.sustained {
+                alerts.push(NetworkAlert {
+                    player_id,
+                    threshold: threshold.name.clone(),
+                    metric: threshold.metric,
+                    value,
+                    limit: threshold.limit,
+                    action: threshold.action,
+                });
+            }
+        }
+
+        if let Some(callback) = self.on_alert.as_mut() {
+            alerts.iter().for_each(callback);
+        }
+        alerts
+    }
+
+    /// 立刻采样所有在线玩家
+    pub fn sample_all(&mut self) {
+        let func = vcmp_func();
+        for player_id in func.get_connected_players() {
+            let alerts = self.record(
+                player_id,
+                func.get_player_ping(player_id),
+                func.network_stats(player_id),
+            );
+            if alerts
+                .iter()
+                .any(|alert| alert.action == ThresholdAction::Kick)
+            {
+                func.kick_player(player_id);
+                self.players.remove(&player_id);
+            }
+        }
+    }
+
+    pub fn on_server_frame(&mut self, event: &ServerFrameEvent) {
+        self.clock.advance(event);
+        if self.clock.now() < self.next_sample {
+            return;
+        }
+        self.next_sample = self.clock.now() + self.interval;
+        self.sample_all();
+    }
+
+    pub fn on_player_disconnect(&mut self, event: &PlayerDisconnectEvent) {
+        self.players.remove(&event.player_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(secs: f64, ping: i32) -> NetworkSample {
+        NetworkSample {
+            at: Duration::from_secs_f64(secs),
+            ping,
+            stats: NetworkStats::default(),
+        }
+    }
+
+    fn stats(packet_loss: f64) -> NetworkStats {
+        NetworkStats {
+            packet_loss_per_second: packet_loss,
+            data_sent_per_second: 100.0,
+            data_resent_per_second: 25.0,
+            ..NetworkStats::default()
+        }
+    }
+
+    /// 让下一次 `record` 的采样时间往后走一秒
+    fn tick(monitor: &mut NetworkMonitor) {
+        monitor.clock.advance(&ServerFrameEvent::from(1.0));
+    }
+
+    #[test]
+    fn ping_slope_fits_a_line() {
+        let line: VecDeque<_> = (0..5)
+            .map(|i| sample(i as f64 * 2.0, 100 + i * 30))
+            .collect();
+        assert!((ping_slope(&line) - 15.0).abs() < 1e-9);
+
+        let falling: VecDeque<_> = [sample(0.0, 300), sample(1.0, 200), sample(2.0, 100)].into();
+        assert!((ping_slope(&falling) + 100.0).abs() < 1e-9);
+
+        // 少于两个采样或者都在同一时间时没有趋势
+        assert_eq!(ping_slope(&[sample(0.0, 100)].into()), 0.0);
+        assert_eq!(
+            ping_slope(&[sample(1.0, 100), sample(1.0, 900)].into()),
+            0.0
+        );
+    }
+
+    #[test]
+    fn report_averages_the_window() {
+        let mut monitor = NetworkMonitor::new(Duration::from_secs(1), 3);
+        for ping in [100, 200, 300, 400] {
+            monitor.record(0, ping, stats(0.1));
+            tick(&mut monitor);
+        }
+        let report = monitor.report(0).unwrap();
+        assert_eq!(report.samples, 3);
+        assert_eq!(report.average_ping, 300.0);
+        assert_eq!(report.max_ping, 400);
+        assert!((report.ping_trend - 100.0).abs() < 1e-9);
+        assert!((report.packet_loss - 0.1).abs() < 1e-9);
+        assert_eq!(report.resend_rate, 0.25);
+        assert_eq!(monitor.latest(0).unwrap().ping, 400);
+        assert!(monitor.report(1).is_none());
+
+        monitor.set_window(1);
+        assert_eq!(monitor.report(0).unwrap().average_ping, 400.0);
+    }
+
+    #[test]
+    fn sustained_threshold_fires_once_per_streak() {
+        let mut monitor = NetworkMonitor::new(Duration::from_secs(1), 1);
+        monitor
+            .add_threshold(NetworkThreshold::new("lag", NetworkMetric::Ping, 250.0).sustained(3));
+        let fired: Vec<usize> = [300, 300, 300, 300, 100, 300, 300, 300]
+            .into_iter()
+            .map(|ping| monitor.record(0, ping, NetworkStats::default()).len())
+            .collect();
+        assert_eq!(fired, vec![0, 0, 1, 0, 0, 0, 0, 1]);
+        // 等于限制不算超过
+        assert!(monitor.record(1, 250, NetworkStats::default()).is_empty());
+    }
+
+    #[test]
+    fn alert_contents_and_callback() {
+        use std::sync::{Arc, Mutex};
+
+        let mut monitor = NetworkMonitor::new(Duration::from_secs(1), 1);
+        monitor.add_threshold(NetworkThreshold::new("loss", NetworkMetric::PacketLoss, 0.2).kick());
+        monitor.add_threshold(NetworkThreshold::new("lag", NetworkMetric::Ping, 500.0));
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        monitor.on_alert(move |alert| sink.lock().unwrap().push(alert.threshold.clone()));
+
+        let alerts = monitor.record(7, 100, stats(0.5));
+        assert_eq!(
+            alerts,
+            vec![NetworkAlert {
+                player_id: 7,
+                threshold: "loss".to_string(),
+                metric: NetworkMetric::PacketLoss,
+                value: 0.5,
+                limit: 0.2,
+                action: ThresholdAction::Kick,
+            }]
+        );
+        monitor.record(7, 900, stats(0.0));
+        assert_eq!(*seen.lock().unwrap(), vec!["loss", "lag"]);
+
+        monitor.clear_thresholds();
+        assert!(monitor.record(7, 900, stats(0.9)).is_empty());
+    }
+}
@@ -0,0 +1,388 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+use crate::events::player::{PlayerConnectEvent, PlayerDisconnectEvent};
+use crate::events::server::ServerFrameEvent;
+use crate::func::PlayerMethods;
+use crate::clock::FrameClock;
+use crate::utils::Color;
+use crate::{PlayerId, vcmp_func};
+
+/// ping / fps 的限制
+///
+/// 警告信息里的 `{value}` `{limit}` `{warnings}` `{max}` 会被替换
+#[derive(Debug, Clone, PartialEq)]
+pub struct PerformanceLimits {
+    /// 平均 ping 超过这个值算违规
+    pub max_ping: Option<i32>,
+    /// 平均 fps 低于这个值算违规
+    pub min_fps: Option<f64>,
+    /// 多久采样一次
+    pub sample_interval: Duration,
+    /// 多少次采样取一次平均
+    pub window: usize,
+    /// 警告几次之后踢出, 0 表示直接踢
+    pub max_warnings: u32,
+    /// 管理员不受限制
+    pub exempt_admins: bool,
+    /// 刚连上的这段时间里不采样
+    pub connect_grace: Duration,
+    pub warning_color: Color,
+    pub ping_warning: String,
+    pub fps_warning: String,
+}
+
+impl Default for PerformanceLimits {
+    fn default() -> Self {
+        Self {
+            max_ping: Some(500),
+            min_fps: None,
+            sample_interval: Duration::from_secs(2),
+            window: 5,
+            max_warnings: 3,
+            exempt_admins: true,
+            connect_grace: Duration::from_secs(30),
+            warning_color: Color::from_rgb(0xFF4040, None),
+            ping_warning: "Your ping is too high ({value}/{limit}), warning {warnings}/{max}"
+                .to_string(),
+            fps_warning: "Your FPS is too low ({value}/{limit}), warning {warnings}/{max}"
+                .to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ViolationKind {
+    Ping,
+    Fps,
+}
+
+/// 一次违规
+#[derive(Debug, Clone, PartialEq)]
+pub struct PerformanceViolation {
+    pub player_id: PlayerId,
+    pub kind: ViolationKind,
+    /// 窗口内的平均值
+    pub value: f64,
+    pub limit: f64,
+    /// 算上这次一共被警告了几次
+    pub warnings: u32,
+    /// 这次违规之后是否被踢出
+    pub kicked: bool,
+}
+
+type ViolationCallback = Box<dyn FnMut(&PerformanceViolation) + Send>;
+
+#[derive(Debug)]
+struct PlayerPerformance {
+    since: Duration,
+    pings: VecDeque<i32>,
+    fps: VecDeque<f64>,
+    warnings: u32,
+}
+
+impl PlayerPerformance {
+    fn new(since: Duration) -> Self {
+        Self {
+            since,
+            pings: VecDeque::new(),
+            fps: VecDeque::new(),
+            warnings: 0,
+        }
+    }
+}
+
+/// 按 ping / fps 警告和踢出玩家
+///
+/// 每个玩家连上之后先等 `connect_grace`, 之后每 `sample_interval` 采样一次,
+/// 凑够 `window` 次采样时检查平均值. 违规之后会清空窗口, 下一次警告需要重新凑够采样
+///
+/// 需要在 `ServerFrame` `PlayerConnect` `PlayerDisconnect` 里调用对应的方法
+pub struct PerformancePolicy {
+    limits: PerformanceLimits,
+    clock: FrameClock,
+    next_sample: Duration,
+    players: HashMap<PlayerId, PlayerPerformance>,
+    exempt: Vec<PlayerId>,
+    on_violation: Option<ViolationCallback>,
+}
+
+impl Default for PerformancePolicy {
+    fn default() -> Self {
+        Self::new(PerformanceLimits::default())
+    }
+}
+
+impl PerformancePolicy {
+    pub fn new(limits: PerformanceLimits) -> Self {
+        Self {
+            next_sample: limits.sample_interval,
+            limits,
+            clock: FrameClock::new(),
+            players: HashMap::new(),
+            exempt: Vec::new(),
+            on_violation: None,
+        }
+    }
+
+    pub fn limits(&self) -> &PerformanceLimits {
+        &self.limits
+    }
+
+    pub fn set_limits(&mut self, limits: PerformanceLimits) {
+        self.limits = limits;
+        for player in self.players.values_mut() {
+            player.pings.clear();
+            player.fps.clear();
+        }
+    }
+
+    /// 违规时调用, 警告信息已经发出去了. 这次要踢出时不发警告, 回调之后再踢
+    pub fn on_violation(&mut self, callback: impl FnMut(&PerformanceViolation) + Send + 'static) {
+        self.on_violation = Some(Box::new(callback));
+    }
+
+    /// 单独豁免某个玩家, 断开时自动取消
+    pub fn set_exempt(&mut self, player_id: PlayerId, exempt: bool) {
+        self.exempt.retain(|&id| id != player_id);
+        if exempt {
+            self.exempt.push(player_id);
+        }
+    }
+
+    pub fn is_exempt(&self, player_id: PlayerId) -> bool {
+        self.exempt.contains(&player_id)
+            || (self.limits.exempt_admins && vcmp_func().is_player_admin(player_id))
+    }
+
+    pub fn warnings(&self, player_id: PlayerId) -> u32 {
+        self.players
+            .get(&player_id)
+            .map(|player| player.warnings)
+            .unwrap_or(0)
+    }
+
+    pub fn reset_warnings(&mut self, player_id: PlayerId) {
+        if let Some(player) = self.players.get_mut(&player_id) {
+            player.warnings = 0;
+        }
+    }
+
+    /// 加一次采样, 返回这次产生的违规
+    ///
+    /// 不会检查豁免和宽限期, 也不会发警告和踢人, 这些由 `on_server_frame` 处理
+    pub fn record(
+        &mut self,
+        player_id: PlayerId,
+        ping: i32,
+        fps: f64,
+    ) -> Vec<PerformanceViolation> {
+        let window = self.limits.window.max(1);
+        let now = self.clock.now();
+        let player = self
+            .players
+            .entry(player_id)
+            .or_insert_with(|| PlayerPerformance::new(now));
+        player.pings.push_back(ping);
+        player.fps.push_back(fps);
+        while player.pings.len() > window {
+            player.pings.pop_front();
+            player.fps.pop_front();
+        }
+        if player.pings.len() < window {
+            return Vec::new();
+        }
+
+        let average_ping =
+            player.pings.iter().map(|&ping| ping as f64).sum::<f64>() / window as f64;
+        let average_fps = player.fps.iter().sum::<f64>() / window as f64;
+        let mut found = Vec::new();
+        if let Some(max_ping) = self.limits.max_ping
+            && average_ping > max_ping as f64
+        {
+            found.push((ViolationKind::Ping, average_ping, max_ping as f64));
+        }
+        if let Some(min_fps) = self.limits.min_fps
+            && average_fps < min_fps
+        {
+            found.push((ViolationKind::Fps, average_fps, min_fps));
+        }
+        if found.is_empty() {
+            return Vec::new();
+        }
+
+        player.pings.clear();
+        player.fps.clear();
+        player.warnings += 1;
+        let kicked = player.warnings > self.limits.max_warnings;
+        found
+            .into_iter()
+            .map(|(kind, value, limit)| PerformanceViolation {
+                player_id,
+                kind,
+                value,
+                limit,
+                warnings: player.warnings,
+                kicked,
+            })
+            .collect()
+    }
+
+    fn warning_text(&self, violation: &PerformanceViolation) -> String {
+        let template = match violation.kind {
+            ViolationKind::Ping => &self.limits.ping_warning,
+            ViolationKind::Fps => &self.limits.fps_warning,
+        };
+        template
+            .replace("{value}", &format!("{:.0}", violation.value))
+            .replace("{limit}", &format!("{:.0}", violation.limit))
+            .replace("{warnings}", &violation.warnings.to_string())
+            .replace("{max}", &self.limits.max_warnings.to_string())
+    }
+
+    fn sample_all(&mut self) {
+        let func = vcmp_func();
+        let now = self.clock.now();
+        for player_id in func.get_connected_players() {
+            let since = self
+                .players
+                .entry(player_id)
+                .or_insert_with(|| PlayerPerformance::new(now))
+                .since;
+            if now < since + self.limits.connect_grace || self.is_exempt(player_id) {
+                continue;
+            }
+
+            let violations = self.record(
+                player_id,
+                func.get_player_ping(player_id),
+                func.get_player_fps(player_id),
+            );
+            for violation in &violations {
+                if !violation.kicked {
+                    let _ = func.send_client_message(
+                        player_id,
+                        self.limits.warning_color,
+                        &self.warning_text(violation),
+                    );
+                }
+                if let Some(callback) = self.on_violation.as_mut() {
+                    callback(violation);
+                }
+            }
+            if violations.iter().any(|violation| violation.kicked) {
+                func.kick_player(player_id);
+                self.players.remove(&player_id);
+            }
+        }
+    }
+
+    pub fn on_server_frame(&mut self, event: &ServerFrameEvent) {
+        self.clock.advance(event);
+        if self.clock.now() < self.next_sample {
+            return;
+        }
+        self.next_sample = self.clock.now() + self.limits.sample_interval;
+        self.sample_all();
+    }
+
+    /// 开始宽限期
+    pub fn on_player_connect(&mut self, event: &PlayerConnectEvent) {
+        self.players
+            .insert(event.player_id, PlayerPerformance::new(self.clock.now()));
+    }
+
+    pub fn on_player_disconnect(&mut self, event: &PlayerDisconnectEvent) {
+        self.players.remove(&event.player_id);
+        self.exempt.retain(|&id| id != event.player_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(max_warnings: u32) -> PerformancePolicy {
+        PerformancePolicy::new(PerformanceLimits {
+            max_ping: Some(300),
+            min_fps: Some(20.0),
+            window: 3,
+            max_warnings,
+            ..PerformanceLimits::default()
+        })
+    }
+
+    #[test]
+    fn waits_for_a_full_window() {
+        let mut policy = policy(3);
+        assert!(policy.record(0, 1000, 60.0).is_empty());
+        assert!(policy.record(0, 1000, 60.0).is_empty());
+        assert_eq!(policy.record(0, 1000, 60.0).len(), 1);
+    }
+
+    #[test]
+    fn uses_the_window_average() {
+        let mut policy = policy(3);
+        // 一次尖峰平均下来没有超
+        assert!(policy.record(0, 100, 60.0).is_empty());
+        assert!(policy.record(0, 100, 60.0).is_empty());
+        assert!(policy.record(0, 650, 60.0).is_empty());
+        // 窗口往前滑, 平均 (100 + 650 + 400) / 3 > 300
+        let violations = policy.record(0, 400, 60.0);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].kind, ViolationKind::Ping);
+        assert!((violations[0].value - 383.333).abs() < 0.01);
+        assert_eq!(violations[0].limit, 300.0);
+    }
+
+    #[test]
+    fn violation_clears_the_window() {
+        let mut policy = policy(3);
+        for _ in 0..3 {
+            policy.record(0, 1000, 60.0);
+        }
+        assert_eq!(policy.warnings(0), 1);
+        assert!(policy.record(0, 1000, 60.0).is_empty());
+        assert!(policy.record(0, 1000, 60.0).is_empty());
+        assert_eq!(policy.record(0, 1000, 60.0)[0].warnings, 2);
+    }
+
+    #[test]
+    fn kicks_after_max_warnings() {
+        let mut policy = policy(2);
+        let mut streak = Vec::new();
+        for _ in 0..9 {
+            streak.extend(policy.record(0, 1000, 60.0));
+        }
+        let kicked: Vec<(u32, bool)> = streak
+            .iter()
+            .map(|violation| (violation.warnings, violation.kicked))
+            .collect();
+        assert_eq!(kicked, vec![(1, false), (2, false), (3, true)]);
+
+        policy.reset_warnings(0);
+        assert_eq!(policy.warnings(0), 0);
+    }
+
+    #[test]
+    fn zero_warnings_kicks_immediately() {
+        let mut policy = policy(0);
+        policy.record(0, 1000, 60.0);
+        policy.record(0, 1000, 60.0);
+        assert!(policy.record(0, 1000, 60.0)[0].kicked);
+    }
+
+    #[test]
+    fn ping_and_fps_in_one_sample() {
+        let mut policy = policy(3);
+        policy.record(0, 1000, 10.0);
+        policy.record(0, 1000, 10.0);
+        let violations = policy.record(0, 1000, 10.0);
+        let kinds: Vec<ViolationKind> = violations.iter().map(|v| v.kind).collect();
+        assert_eq!(kinds, vec![ViolationKind::Ping, ViolationKind::Fps]);
+        // 同一次采样只算一次警告
+        assert!(violations.iter().all(|violation| violation.warnings == 1));
+        // 不同玩家分开算
+        assert_eq!(policy.warnings(1), 0);
+    }
+}
@@ -0,0 +1,7 @@
+//! 和客户端脚本通信用的东西
+
+pub mod rpc;
+pub mod stream;
+
+pub use rpc::{RpcEndpoint, RpcError, RpcValue};
+pub use stream::{StreamError, StreamReader, StreamResult, StreamWriter};
@@ -0,0 +1,674 @@
+//! 基于 client script data 的 rpc
+//!
+//! 服务端和客户端两边是对称的, 都可以调用对方注册好的过程. 每个包都用 Stream 的格式编码:
+//!
+//! ```text
+//! int    magic (0x56525043, "VRPC")
+//! byte   kind (0 调用, 1 返回, 2 出错)
+//! int    id
+//! 调用:  string 过程名, byte 参数个数, 参数...
+//! 返回:  byte 返回值个数, 返回值...
+//! 出错:  string 错误信息
+//! 值:    byte 类型 (0 null, 1 bool, 2 int, 3 float, 4 string) + 对应的内容
+//! ```
+//!
+//! 不是以 magic 开头的包会被忽略, 可以和别的 client script data 共存
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Display;
+use std::time::Duration;
+
+use crate::events::player::{ClientScriptDataEvent, PlayerDisconnectEvent};
+use crate::events::server::ServerFrameEvent;
+use crate::func::PlayerMethods;
+use crate::clock::FrameClock;
+use crate::script::stream::{StreamError, StreamReader, StreamResult, StreamWriter};
+use crate::{PlayerId, VcmpError, VcmpResult, vcmp_func};
+
+pub const RPC_MAGIC: i32 = 0x5652_5043;
+
+const KIND_CALL: u8 = 0;
+const KIND_REPLY: u8 = 1;
+const KIND_ERROR: u8 = 2;
+
+/// rpc 的参数和返回值
+#[derive(Debug, Clone, PartialEq)]
+pub enum RpcValue {
+    Null,
+    Bool(bool),
+    Int(i32),
+    Float(f32),
+    String(String),
+}
+
+impl RpcValue {
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            RpcValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_int(&self) -> Option<i32> {
+        match self {
+            RpcValue::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// int 也会被当成 float
+    pub fn as_float(&self) -> Option<f32> {
+        match self {
+            RpcValue::Float(value) => Some(*value),
+            RpcValue::Int(value) => Some(*value as f32),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            RpcValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    fn write(&self, writer: &mut StreamWriter) -> StreamResult<()> {
+        match self {
+            RpcValue::Null => {
+                writer.write_byte(0);
+            }
+            RpcValue::Bool(value) => {
+                writer.write_byte(1).write_bool(*value);
+            }
+            RpcValue::Int(value) => {
+                writer.write_byte(2).write_int(*value);
+            }
+            RpcValue::Float(value) => {
+                writer.write_byte(3).write_float(*value);
+            }
+            RpcValue::String(value) => {
+                writer.write_byte(4).write_string(value)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn read(reader: &mut StreamReader) -> RpcResult<Self> {
+        Ok(match reader.read_byte()? {
+            0 => RpcValue::Null,
+            1 => RpcValue::Bool(reader.read_bool()?),
+            2 => RpcValue::Int(reader.read_int()?),
+            3 => RpcValue::Float(reader.read_float()?),
+            4 => RpcValue::String(reader.read_string()?),
+            tag => return Err(RpcError::InvalidValue(tag)),
+        })
+    }
+}
+
+impl From<bool> for RpcValue {
+    fn from(value: bool) -> Self {
+        RpcValue::Bool(value)
+    }
+}
+
+impl From<i32> for RpcValue {
+    fn from(value: i32) -> Self {
+        RpcValue::Int(value)
+    }
+}
+
+impl From<f32> for RpcValue {
+    fn from(value: f32) -> Self {
+        RpcValue::Float(value)
+    }
+}
+
+impl From<&str> for RpcValue {
+    fn from(value: &str) -> Self {
+        RpcValue::String(value.to_string())
+    }
+}
+
+impl From<String> for RpcValue {
+    fn from(value: String) -> Self {
+        RpcValue::String(value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RpcError {
+    /// 对方没有在超时时间内回复
+    Timeout,
+    /// 等回复的时候玩家断开了
+    Disconnected,
+    /// 对方返回的错误 (包括找不到过程)
+    Remote(String),
+    /// 发送失败
+    Send(VcmpError),
+    /// 包格式不对
+    Decode(StreamError),
+    /// 未知的值类型
+    InvalidValue(u8),
+    /// 参数或者返回值超过 255 个
+    TooManyValues(usize),
+}
+
+impl std::error::Error for RpcError {}
+
+impl Display for RpcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RpcError::Timeout => write!(f, "调用超时"),
+            RpcError::Disconnected => write!(f, "玩家已断开"),
+            RpcError::Remote(message) => write!(f, "对方返回错误: {message}"),
+            RpcError::Send(err) => write!(f, "发送失败: {err}"),
+            RpcError::Decode(err) => write!(f, "解码失败: {err}"),
+            RpcError::InvalidValue(tag) => write!(f, "未知的值类型 {tag}"),
+            RpcError::TooManyValues(count) => write!(f, "值太多 ({count})"),
+        }
+    }
+}
+
+impl From<StreamError> for RpcError {
+    fn from(value: StreamError) -> Self {
+        RpcError::Decode(value)
+    }
+}
+
+impl From<VcmpError> for RpcError {
+    fn from(value: VcmpError) -> Self {
+        RpcError::Send(value)
+    }
+}
+
+pub type RpcResult<T> = Result<T, RpcError>;
+
+/// 一个 rpc 包
+#[derive(Debug, Clone, PartialEq)]
+pub enum RpcMessage {
+    Call {
+        id: i32,
+        name: String,
+        args: Vec<RpcValue>,
+    },
+    Reply {
+        id: i32,
+        values: Vec<RpcValue>,
+    },
+    Error {
+        id: i32,
+        message: String,
+    },
+}
+
+impl RpcMessage {
+    pub fn encode(&self) -> RpcResult<Vec<u8>> {
+        let mut writer = StreamWriter::new();
+        writer.write_int(RPC_MAGIC);
+        match self {
+            RpcMessage::Call { id, name, args } => {
+                writer.write_byte(KIND_CALL).write_int(*id);
+                writer.write_string(name)?;
+                write_values(&mut writer, args)?;
+            }
+            RpcMessage::Reply { id, values } => {
+                writer.write_byte(KIND_REPLY).write_int(*id);
+                write_values(&mut writer, values)?;
+            }
+            RpcMessage::Error { id, message } => {
+                writer.write_byte(KIND_ERROR).write_int(*id);
+                writer.write_string(message)?;
+            }
+        }
+        Ok(writer.into_bytes())
+    }
+
+    /// 不是 rpc 包的时候返回 Ok(None)
+    pub fn decode(data: &[u8]) -> RpcResult<Option<Self>> {
+        let mut reader = StreamReader::new(data);
+        if reader.read_int().ok() != Some(RPC_MAGIC) {
+            return Ok(None);
+        }
+        let kind = reader.read_byte()?;
+        let id = reader.read_int()?;
+        Ok(Some(match kind {
+            KIND_CALL => RpcMessage::Call {
+                id,
+                name: reader.read_string()?,
+                args: read_values(&mut reader)?,
+            },
+            KIND_REPLY => RpcMessage::Reply {
+                id,
+                values: read_values(&mut reader)?,
+            },
+            KIND_ERROR => RpcMessage::Error {
+                id,
+                message: reader.read_string()?,
+            },
+            _ => return Ok(None),
+        }))
+    }
+}
+
+fn write_values(writer: &mut StreamWriter, values: &[RpcValue]) -> RpcResult<()> {
+    let count = u8::try_from(values.len()).map_err(|_| RpcError::TooManyValues(values.len()))?;
+    writer.write_byte(count);
+    for value in values {
+        value.write(writer)?;
+    }
+    Ok(())
+}
+
+fn read_values(reader: &mut StreamReader) -> RpcResult<Vec<RpcValue>> {
+    let count = reader.read_byte()?;
+    (0..count).map(|_| RpcValue::read(reader)).collect()
+}
+
+/// rpc 包怎么发出去
+pub trait RpcTransport {
+    fn send(&mut self, player_id: PlayerId, data: &[u8]) -> VcmpResult<()>;
+}
+
+/// 通过 `send_client_script_data` 发给客户端
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ClientScriptTransport;
+
+impl RpcTransport for ClientScriptTransport {
+    fn send(&mut self, player_id: PlayerId, data: &[u8]) -> VcmpResult<()> {
+        vcmp_func().send_client_script_data(player_id, data)
+    }
+}
+
+/// 不经过服务器, 只是把包存起来
+///
+/// 把一边 `drain` 出来的包喂给另一边的 `handle_data`, 就能在没有服务器的时候跑通两端
+#[derive(Debug, Clone, Default)]
+pub struct LoopbackTransport {
+    outbox: VecDeque<(PlayerId, Vec<u8>)>,
+}
+
+impl LoopbackTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn drain(&mut self) -> Vec<(PlayerId, Vec<u8>)> {
+        self.outbox.drain(..).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.outbox.is_empty()
+    }
+}
+
+impl RpcTransport for LoopbackTransport {
+    fn send(&mut self, player_id: PlayerId, data: &[u8]) -> VcmpResult<()> {
+        self.outbox.push_back((player_id, data.to_vec()));
+        Ok(())
+    }
+}
+
+type Procedure = Box<dyn FnMut(PlayerId, Vec<RpcValue>) -> Result<Vec<RpcValue>, String> + Send>;
+type ReplyCallback = Box<dyn FnOnce(RpcResult<Vec<RpcValue>>) + Send>;
+
+struct PendingCall {
+    deadline: Duration,
+    callback: ReplyCallback,
+}
+
+/// rpc 的一端
+///
+/// 需要在 `ClientScriptData` `ServerFrame` `PlayerDisconnect` 里调用对应的方法
+pub struct RpcEndpoint<T: RpcTransport = ClientScriptTransport> {
+    transport: T,
+    clock: FrameClock,
+    timeout: Duration,
+    next_id: i32,
+    procedures: HashMap<String, Procedure>,
+    pending: HashMap<PlayerId, HashMap<i32, PendingCall>>,
+}
+
+impl Default for RpcEndpoint<ClientScriptTransport> {
+    fn default() -> Self {
+        Self::new(ClientScriptTransport)
+    }
+}
+
+impl<T: RpcTransport> RpcEndpoint<T> {
+    pub fn new(transport: T) -> Self {
+        Self {
+            transport,
+            clock: FrameClock::new(),
+            timeout: Duration::from_secs(10),
+            next_id: 1,
+            procedures: HashMap::new(),
+            pending: HashMap::new(),
+        }
+    }
+
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    pub fn transport_mut(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
+    /// 调用的超时时间, 默认 10 秒
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// 注册一个给对方调用的过程, 返回 Err 时对方会收到错误信息
+    pub fn register(
+        &mut self,
+        name: &str,
+        procedure: impl FnMut(PlayerId, Vec<RpcValue>) -> Result<Vec<RpcValue>, String> + Send + 'static,
+    ) {
+        self.procedures
+            .insert(name.to_string(), Box::new(procedure));
+    }
+
+    pub fn unregister(&mut self, name: &str) -> bool {
+        self.procedures.remove(name).is_some()
+    }
+
+    /// 调用对方的过程, 回复 / 超时 / 玩家断开的时候会调用 callback
+    ///
+    /// 发送失败时直接返回错误, 不会调用 callback
+    pub fn call(
+        &mut self,
+        player_id: PlayerId,
+        name: &str,
+        args: Vec<RpcValue>,
+        callback: impl FnOnce(RpcResult<Vec<RpcValue>>) + Send + 'static,
+    ) -> RpcResult<i32> {
+        let id = self.next_id;
+        self.next_id = self.next_id.checked_add(1).unwrap_or(1);
+        let data = RpcMessage::Call {
+            id,
+            name: name.to_string(),
+            args,
+        }
+        .encode()?;
+        self.transport.send(player_id, &data)?;
+        self.pending.entry(player_id).or_default().insert(
+            id,
+            PendingCall {
+                deadline: self.clock.now() + self.timeout,
+                callback: Box::new(callback),
+            },
+        );
+        Ok(id)
+    }
+
+    /// 还在等这个玩家回复的调用数量
+    pub fn pending_count(&self, player_id: PlayerId) -> usize {
+        self.pending
+            .get(&player_id)
+            .map(|calls| calls.len())
+            .unwrap_or(0)
+    }
+
+    /// 处理收到的数据, 是 rpc 包的话返回 true
+    pub fn handle_data(&mut self, player_id: PlayerId, data: &[u8]) -> bool {
+        let message = match RpcMessage::decode(data) {
+            Ok(Some(message)) => message,
+            Ok(None) => return false,
+            // magic 对上了但是内容坏了, 还是算 rpc 包
+            Err(_) => return true,
+        };
+        match message {
+            RpcMessage::Call { id, name, args } => {
+                let reply = match self.procedures.get_mut(&name) {
+                    Some(procedure) => match procedure(player_id, args) {
+                        Ok(values) => RpcMessage::Reply { id, values },
+                        Err(message) => RpcMessage::Error { id, message },
+                    },
+                    None => RpcMessage::Error {
+                        id,
+                        message: format!("unknown procedure {name}"),
+                    },
+                };
+                if let Ok(data) = reply.encode() {
+                    let _ = self.transport.send(player_id, &data);
+                }
+            }
+            RpcMessage::Reply { id, values } => {
+                if let Some(call) = self.take_pending(player_id, id) {
+                    (call.callback)(Ok(values));
+                }
+            }
+            RpcMessage::Error { id, message } => {
+                if let Some(call) = self.take_pending(player_id, id) {
+                    (call.callback)(Err(RpcError::Remote(message)));
+                }
+            }
+        }
+        true
+    }
+
+    pub fn on_client_script_data(&mut self, event: &ClientScriptDataEvent) -> bool {
+        self.handle_data(event.player_id, &event.data)
+    }
+
+    /// 处理超时
+    pub fn on_server_frame(&mut self, event: &ServerFrameEvent) {
+        self.clock.advance(event);
+        let now = self.clock.now();
+        let mut expired = Vec::new();
+        for calls in self.pending.values_mut() {
+            let ids: Vec<i32> = calls
+                .iter()
+                .filter(|(_, call)| call.deadline <= now)
+                .map(|(id, _)| *id)
+                .collect();
+            for id in ids {
+                if let Some(call) = calls.remove(&id) {
+                    expired.push(call);
+                }
+            }
+        }
+        self.pending.retain(|_, calls| !calls.is_empty());
+        for call in expired {
+            (call.callback)(Err(RpcError::Timeout));
+        }
+    }
+
+    /// 这个玩家所有还没回复的调用都会以 Disconnected 结束
+    pub fn on_player_disconnect(&mut self, event: &PlayerDisconnectEvent) {
+        if let Some(calls) = self.pending.remove(&event.player_id) {
+            for (_, call) in calls {
+                (call.callback)(Err(RpcError::Disconnected));
+            }
+        }
+    }
+
+    fn take_pending(&mut self, player_id: PlayerId, id: i32) -> Option<PendingCall> {
+        let calls = self.pending.get_mut(&player_id)?;
+        let call = calls.remove(&id);
+        if calls.is_empty() {
+            self.pending.remove(&player_id);
+        }
+        call
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    type Endpoint = RpcEndpoint<LoopbackTransport>;
+    type Slot = Arc<Mutex<Option<RpcResult<Vec<RpcValue>>>>>;
+
+    const PLAYER: PlayerId = 3;
+
+    /// 把 `from` 发出去的包交给 `to`
+    fn pump(from: &mut Endpoint, to: &mut Endpoint) {
+        for (player_id, data) in from.transport_mut().drain() {
+            assert_eq!(player_id, PLAYER);
+            assert!(to.handle_data(player_id, &data));
+        }
+    }
+
+    fn call(endpoint: &mut Endpoint, name: &str, args: Vec<RpcValue>) -> Slot {
+        let slot: Slot = Arc::default();
+        let result = slot.clone();
+        endpoint
+            .call(PLAYER, name, args, move |reply| {
+                *result.lock().unwrap() = Some(reply);
+            })
+            .unwrap();
+        slot
+    }
+
+    fn taken(slot: &Slot) -> Option<RpcResult<Vec<RpcValue>>> {
+        slot.lock().unwrap().take()
+    }
+
+    fn pair() -> (Endpoint, Endpoint) {
+        let server = Endpoint::new(LoopbackTransport::new());
+        let mut client = Endpoint::new(LoopbackTransport::new());
+        client.register("add", |_, args| {
+            let sum = args
+                .iter()
+                .map(|arg| arg.as_int().ok_or("not an int"))
+                .sum::<Result<i32, _>>()?;
+            Ok(vec![RpcValue::Int(sum)])
+        });
+        (server, client)
+    }
+
+    #[test]
+    fn call_gets_reply() {
+        let (mut server, mut client) = pair();
+        let slot = call(&mut server, "add", vec![1.into(), 2.into()]);
+        assert_eq!(server.pending_count(PLAYER), 1);
+
+        pump(&mut server, &mut client);
+        pump(&mut client, &mut server);
+
+        assert_eq!(taken(&slot), Some(Ok(vec![RpcValue::Int(3)])));
+        assert_eq!(server.pending_count(PLAYER), 0);
+        assert!(server.transport().is_empty());
+    }
+
+    #[test]
+    fn error_reply() {
+        let (mut server, mut client) = pair();
+        let unknown = call(&mut server, "missing", vec![]);
+        let failed = call(&mut server, "add", vec!["x".into()]);
+
+        pump(&mut server, &mut client);
+        pump(&mut client, &mut server);
+
+        assert_eq!(
+            taken(&unknown),
+            Some(Err(RpcError::Remote(
+                "unknown procedure missing".to_string()
+            )))
+        );
+        assert_eq!(
+            taken(&failed),
+            Some(Err(RpcError::Remote("not an int".to_string())))
+        );
+        assert_eq!(server.pending_count(PLAYER), 0);
+    }
+
+    #[test]
+    fn call_times_out() {
+        let (mut server, _) = pair();
+        server.set_timeout(Duration::from_secs(1));
+        let slot = call(&mut server, "add", vec![]);
+
+        server.on_server_frame(&ServerFrameEvent::from(0.5));
+        assert_eq!(taken(&slot), None);
+        server.on_server_frame(&ServerFrameEvent::from(0.6));
+        assert_eq!(taken(&slot), Some(Err(RpcError::Timeout)));
+        assert_eq!(server.pending_count(PLAYER), 0);
+    }
+
+    #[test]
+    fn late_reply_is_ignored() {
+        let (mut server, mut client) = pair();
+        server.set_timeout(Duration::from_secs(1));
+        let slot = call(&mut server, "add", vec![]);
+        server.on_server_frame(&ServerFrameEvent::from(2.0));
+        assert_eq!(taken(&slot), Some(Err(RpcError::Timeout)));
+
+        pump(&mut server, &mut client);
+        pump(&mut client, &mut server);
+        assert_eq!(taken(&slot), None);
+    }
+
+    #[test]
+    fn disconnect_fails_pending_calls() {
+        let (mut server, _) = pair();
+        let first = call(&mut server, "add", vec![]);
+        let second = call(&mut server, "add", vec![]);
+        assert_eq!(server.pending_count(PLAYER), 2);
+
+        server.on_player_disconnect(&PlayerDisconnectEvent::from((PLAYER, 0)));
+
+        assert_eq!(taken(&first), Some(Err(RpcError::Disconnected)));
+        assert_eq!(taken(&second), Some(Err(RpcError::Disconnected)));
+        assert_eq!(server.pending_count(PLAYER), 0);
+    }
+
+    #[test]
+    fn message_round_trip() {
+        let messages = [
+            RpcMessage::Call {
+                id: 7,
+                name: "测试".to_string(),
+                args: vec![
+                    RpcValue::Null,
+                    RpcValue::Bool(true),
+                    RpcValue::Int(-42),
+                    RpcValue::Float(1.5),
+                    RpcValue::String("hello".to_string()),
+                ],
+            },
+            RpcMessage::Reply {
+                id: i32::MAX,
+                values: vec![],
+            },
+            RpcMessage::Error {
+                id: -1,
+                message: "boom".to_string(),
+            },
+        ];
+        for message in messages {
+            let data = message.encode().unwrap();
+            assert_eq!(RpcMessage::decode(&data).unwrap(), Some(message));
+        }
+    }
+
+    #[test]
+    fn decode_ignores_other_data() {
+        assert_eq!(RpcMessage::decode(&[]).unwrap(), None);
+        assert_eq!(RpcMessage::decode(&[0, 0, 0, 1, 2, 3]).unwrap(), None);
+
+        let data = RpcMessage::Reply {
+            id: 1,
+            values: vec![RpcValue::Int(1)],
+        }
+        .encode()
+        .unwrap();
+        assert!(RpcMessage::decode(&data[..data.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn too_many_values() {
+        let message = RpcMessage::Reply {
+            id: 1,
+            values: vec![RpcValue::Null; 256],
+        };
+        assert!(matches!(
+            message.encode(),
+            Err(RpcError::TooManyValues(256))
+        ));
+    }
+}
@@ -0,0 +1,292 @@
+//! 和客户端脚本的 `Stream` 一样的二进制格式
+//!
+//! - byte: 1 字节
+//! - int: 4 字节有符号, 大端
+//! - float: 4 字节 IEEE754, 大端
+//! - string: 2 字节大端的长度 + gbk 编码的内容 (不带 \0)
+
+use std::fmt::Display;
+
+use crate::encodes::{decode_gbk, encode_to_gbk};
+use crate::events::player::ClientScriptDataEvent;
+use crate::func::PlayerMethods;
+use crate::{PlayerId, VcmpResult, vcmp_func};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamError {
+    /// 剩下的数据不够读
+    UnexpectedEnd { needed: usize, remaining: usize },
+    /// 字符串编码之后超过 u16 能表示的长度
+    StringTooLong(usize),
+}
+
+impl std::error::Error for StreamError {}
+
+impl Display for StreamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StreamError::UnexpectedEnd { needed, remaining } => {
+                write!(f, "数据不足, 需要 {needed} 字节, 只剩 {remaining} 字节")
+            }
+            StreamError::StringTooLong(len) => write!(f, "字符串过长 ({len} 字节)"),
+        }
+    }
+}
+
+pub type StreamResult<T> = Result<T, StreamError>;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StreamWriter {
+    buf: Vec<u8>,
+}
+
+impl StreamWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn write_byte(&mut self, value: u8) -> &mut Self {
+        self.buf.push(value);
+        self
+    }
+
+    pub fn write_bool(&mut self, value: bool) -> &mut Self {
+        self.write_byte(value as u8)
+    }
+
+    pub fn write_int(&mut self, value: i32) -> &mut Self {
+        self.buf.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    pub fn write_float(&mut self, value: f32) -> &mut Self {
+        self.buf.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    pub fn write_string(&mut self, value: &str) -> StreamResult<&mut Self> {
+        let data = encode_to_gbk(value);
+        let len = u16::try_from(data.len()).map_err(|_| StreamError::StringTooLong(data.len()))?;
+        self.buf.extend_from_slice(&len.to_be_bytes());
+        self.buf.extend_from_slice(&data);
+        Ok(self)
+    }
+
+    /// 直接追加原始字节
+    pub fn write_bytes(&mut self, data: &[u8]) -> &mut Self {
+        self.buf.extend_from_slice(data);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.buf.clear();
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+
+    /// 通过 `send_client_script_data` 发给玩家
+    pub fn send(&self, player_id: PlayerId) -> VcmpResult<()> {
+        vcmp_func().send_client_script_data(player_id, &self.buf)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StreamReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> StreamReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.data.len() - self.pos
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.remaining() == 0
+    }
+
+    fn take(&mut self, len: usize) -> StreamResult<&'a [u8]> {
+        if self.remaining() < len {
+            return Err(StreamError::UnexpectedEnd {
+                needed: len,
+                remaining: self.remaining(),
+            });
+        }
+        let data = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(data)
+    }
+
+    fn take_array<const N: usize>(&mut self) -> StreamResult<[u8; N]> {
+        let mut array = [0u8; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    pub fn read_byte(&mut self) -> StreamResult<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn read_bool(&mut self) -> StreamResult<bool> {
+        Ok(self.read_byte()? != 0)
+    }
+
+    pub fn read_int(&mut self) -> StreamResult<i32> {
+        Ok(i32::from_be_bytes(self.take_array()?))
+    }
+
+    pub fn read_float(&mut self) -> StreamResult<f32> {
+        Ok(f32::from_be_bytes(self.take_array()?))
+    }
+
+    /// 读失败的时候不会移动位置
+    pub fn read_string(&mut self) -> StreamResult<String> {
+        let start = self.pos;
+        let len = u16::from_be_bytes(self.take_array()?) as usize;
+        match self.take(len) {
+            Ok(data) => Ok(decode_gbk(data)),
+            Err(err) => {
+                self.pos = start;
+                Err(err)
+            }
+        }
+    }
+
+    /// 读取剩下所有的字节
+    pub fn read_remaining(&mut self) -> &'a [u8] {
+        let data = &self.data[self.pos..];
+        self.pos = self.data.len();
+        data
+    }
+}
+
+impl<'a> From<&'a ClientScriptDataEvent> for StreamReader<'a> {
+    fn from(value: &'a ClientScriptDataEvent) -> Self {
+        Self::new(&value.data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_bytes() {
+        let mut writer = StreamWriter::new();
+        writer
+            .write_byte(0xAB)
+            .write_bool(true)
+            .write_int(0x0102_0304)
+            .write_int(-2)
+            .write_float(1.0);
+        writer.write_string("中a").unwrap();
+        assert_eq!(
+            writer.as_bytes(),
+            [
+                0xAB, 0x01, //
+                0x01, 0x02, 0x03, 0x04, //
+                0xFF, 0xFF, 0xFF, 0xFE, //
+                0x3F, 0x80, 0x00, 0x00, //
+                0x00, 0x03, 0xD6, 0xD0, b'a',
+            ]
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut writer = StreamWriter::new();
+        writer
+            .write_byte(7)
+            .write_bool(false)
+            .write_int(i32::MIN)
+            .write_float(-3.25);
+        writer.write_string("你好, world").unwrap();
+        writer.write_string("").unwrap();
+        writer.write_bytes(&[1, 2, 3]);
+
+        let bytes = writer.into_bytes();
+        let mut reader = StreamReader::new(&bytes);
+        assert_eq!(reader.read_byte(), Ok(7));
+        assert_eq!(reader.read_bool(), Ok(false));
+        assert_eq!(reader.read_int(), Ok(i32::MIN));
+        assert_eq!(reader.read_float(), Ok(-3.25));
+        assert_eq!(reader.read_string().as_deref(), Ok("你好, world"));
+        assert_eq!(reader.read_string().as_deref(), Ok(""));
+        assert_eq!(reader.read_remaining(), [1, 2, 3]);
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn truncated_input() {
+        let mut reader = StreamReader::new(&[0x00, 0x01, 0x02]);
+        assert_eq!(
+            reader.read_int(),
+            Err(StreamError::UnexpectedEnd {
+                needed: 4,
+                remaining: 3
+            })
+        );
+        assert_eq!(reader.position(), 0);
+
+        let mut reader = StreamReader::new(&[]);
+        assert!(matches!(
+            reader.read_byte(),
+            Err(StreamError::UnexpectedEnd { .. })
+        ));
+        assert!(matches!(
+            reader.read_float(),
+            Err(StreamError::UnexpectedEnd { .. })
+        ));
+
+        let mut reader = StreamReader::new(&[0x00]);
+        assert!(matches!(
+            reader.read_string(),
+            Err(StreamError::UnexpectedEnd { .. })
+        ));
+
+        // 长度写了 5, 内容只有 2 字节, 读失败之后位置不变
+        let mut reader = StreamReader::new(&[0x00, 0x05, b'a', b'b']);
+        assert_eq!(
+            reader.read_string(),
+            Err(StreamError::UnexpectedEnd {
+                needed: 5,
+                remaining: 2
+            })
+        );
+        assert_eq!(reader.position(), 0);
+    }
+
+    #[test]
+    fn string_too_long() {
+        let mut writer = StreamWriter::new();
+        let long = "a".repeat(u16::MAX as usize + 1);
+        assert_eq!(
+            writer.write_string(&long).map(|_| ()),
+            Err(StreamError::StringTooLong(long.len()))
+        );
+        assert!(writer.is_empty());
+        assert!(writer.write_string(&long[1..]).is_ok());
+    }
+}
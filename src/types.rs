@@ -1,3 +1,7 @@
+pub mod ip_range;
+
+pub use ip_range::{IpRange, IpRangeError};
+
 /// 载具ID
 pub type VehicleId = i32;
 /// 玩家ID
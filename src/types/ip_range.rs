@@ -0,0 +1,358 @@
+use std::fmt::Display;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IpRangeError {
+    /// 不是合法的地址
+    InvalidAddress(String),
+    /// `/` 后面的前缀长度不对
+    InvalidPrefix(String),
+    /// `*` 只能出现在 IPv4 地址末尾的几段
+    InvalidWildcard(String),
+}
+
+impl std::error::Error for IpRangeError {}
+
+impl Display for IpRangeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IpRangeError::InvalidAddress(value) => write!(f, "invalid address: {value}"),
+            IpRangeError::InvalidPrefix(value) => write!(f, "invalid prefix: {value}"),
+            IpRangeError::InvalidWildcard(value) => write!(f, "invalid wildcard: {value}"),
+        }
+    }
+}
+
+/// 一段 ip
+///
+/// 支持三种写法:
+/// - 单个地址 `1.2.3.4` `::1`
+/// - CIDR `10.0.0.0/8` `2001:db8::/32`
+/// - IPv4 通配符 `1.2.3.*` `1.2.*.*`
+///
+/// IPv4-mapped 的 IPv6 地址 (`::ffff:1.2.3.4`) 会被当成 IPv4 处理
+///
+/// 比较和哈希只看网络和前缀, `1.2.3.*` 和 `1.2.3.0/24` 是同一个段
+#[derive(Debug, Clone, Copy)]
+pub struct IpRange {
+    network: IpAddr,
+    prefix: u8,
+    wildcard: bool,
+}
+
+fn normalize(addr: IpAddr) -> IpAddr {
+    match addr {
+        IpAddr::V6(v6) => v6
+            .to_ipv4_mapped()
+            .map(IpAddr::V4)
+            .unwrap_or(IpAddr::V6(v6)),
+        v4 => v4,
+    }
+}
+
+fn max_prefix(addr: &IpAddr) -> u8 {
+    match addr {
+        IpAddr::V4(_) => 32,
+        IpAddr::V6(_) => 128,
+    }
+}
+
+fn mask(addr: IpAddr, prefix: u8) -> IpAddr {
+    match addr {
+        IpAddr::V4(v4) => {
+            let bits = u32::from(v4);
+            let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+            IpAddr::V4(Ipv4Addr::from(bits & mask))
+        }
+        IpAddr::V6(v6) => {
+            let bits = u128::from(v6);
+            let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+            IpAddr::V6(Ipv6Addr::from(bits & mask))
+        }
+    }
+}
+
+impl IpRange {
+    /// 只包含一个地址
+    pub fn single(addr: IpAddr) -> Self {
+        let network = normalize(addr);
+        Self {
+            prefix: max_prefix(&network),
+            network,
+            wildcard: false,
+        }
+    }
+
+    /// 超出范围的前缀长度会被截到最大值
+    pub fn cidr(addr: IpAddr, prefix: u8) -> Self {
+        let addr = normalize(addr);
+        let prefix = prefix.min(max_prefix(&addr));
+        Self {
+            network: mask(addr, prefix),
+            prefix,
+            wildcard: false,
+        }
+    }
+
+    pub fn network(&self) -> IpAddr {
+        self.network
+    }
+
+    pub fn prefix(&self) -> u8 {
+        self.prefix
+    }
+
+    /// 前缀为 0, 包含所有 IPv4 或者所有 IPv6 地址
+    pub fn is_everything(&self) -> bool {
+        self.prefix == 0
+    }
+
+    /// 只包含一个地址的时候返回它
+    pub fn as_single(&self) -> Option<IpAddr> {
+        (self.prefix == max_prefix(&self.network)).then_some(self.network)
+    }
+
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        let addr = normalize(addr);
+        addr.is_ipv4() == self.network.is_ipv4() && mask(addr, self.prefix) == self.network
+    }
+
+    /// 不是合法地址的时候返回 false
+    pub fn contains_str(&self, addr: &str) -> bool {
+        addr.trim()
+            .parse::<IpAddr>()
+            .is_ok_and(|addr| self.contains(addr))
+    }
+
+    /// 服务器自带的封禁列表能接受的写法, 单个地址和 IPv4 通配符
+    pub fn native_form(&self) -> Option<String> {
+        (self.as_single().is_some() || self.wildcard).then(|| self.to_string())
+    }
+
+    fn parse_wildcard(value: &str) -> Result<Self, IpRangeError> {
+        let invalid = || IpRangeError::InvalidWildcard(value.to_string());
+        let parts: Vec<&str> = value.split('.').collect();
+        if parts.len() != 4 {
+            return Err(invalid());
+        }
+        let mut octets = [0u8; 4];
+        let mut prefix = 0u8;
+        let mut in_wildcard = false;
+        for (octet, part) in octets.iter_mut().zip(&parts) {
+            if *part == "*" {
+                in_wildcard = true;
+                continue;
+            }
+            if in_wildcard {
+                return Err(invalid());
+            }
+            *octet = part.parse().map_err(|_| invalid())?;
+            prefix += 8;
+        }
+        Ok(Self {
+            network: IpAddr::V4(Ipv4Addr::from(octets)),
+            prefix,
+            wildcard: true,
+        })
+    }
+}
+
+impl FromStr for IpRange {
+    type Err = IpRangeError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        if value.contains('*') {
+            return Self::parse_wildcard(value);
+        }
+        match value.split_once('/') {
+            Some((addr, prefix)) => {
+                let addr = addr
+                    .parse::<IpAddr>()
+                    .map_err(|_| IpRangeError::InvalidAddress(value.to_string()))?;
+                let prefix = prefix
+                    .parse::<u8>()
+                    .ok()
+                    .filter(|&prefix| prefix <= max_prefix(&addr))
+                    .ok_or_else(|| IpRangeError::InvalidPrefix(value.to_string()))?;
+                // IPv4-mapped 的地址转成 IPv4 之后前缀要减掉 96
+                let prefix = match (addr, normalize(addr)) {
+                    (IpAddr::V6(_), IpAddr::V4(_)) => prefix
+                        .checked_sub(96)
+                        .ok_or_else(|| IpRangeError::InvalidPrefix(value.to_string()))?,
+                    _ => prefix,
+                };
+                Ok(Self::cidr(addr, prefix))
+            }
+            None => value
+                .parse::<IpAddr>()
+                .map(Self::single)
+                .map_err(|_| IpRangeError::InvalidAddress(value.to_string())),
+        }
+    }
+}
+
+impl PartialEq for IpRange {
+    fn eq(&self, other: &Self) -> bool {
+        self.network == other.network && self.prefix == other.prefix
+    }
+}
+
+impl Eq for IpRange {}
+
+impl std::hash::Hash for IpRange {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.network.hash(state);
+        self.prefix.hash(state);
+    }
+}
+
+impl From<IpAddr> for IpRange {
+    fn from(value: IpAddr) -> Self {
+        Self::single(value)
+    }
+}
+
+impl Display for IpRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.wildcard
+            && let IpAddr::V4(v4) = self.network
+        {
+            let known = (self.prefix / 8) as usize;
+            let parts: Vec<String> = v4
+                .octets()
+                .iter()
+                .enumerate()
+                .map(|(index, octet)| {
+                    if index < known {
+                        octet.to_string()
+                    } else {
+                        "*".to_string()
+                    }
+                })
+                .collect();
+            return write!(f, "{}", parts.join("."));
+        }
+        if self.as_single().is_some() {
+            write!(f, "{}", self.network)
+        } else {
+            write!(f, "{}/{}", self.network, self.prefix)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(value: &str) -> IpRange {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn single_address() {
+        let single = range(" 1.2.3.4 ");
+        assert_eq!(single.prefix(), 32);
+        assert_eq!(single.as_single(), Some("1.2.3.4".parse().unwrap()));
+        assert!(single.contains_str("1.2.3.4"));
+        assert!(!single.contains_str("1.2.3.5"));
+        assert_eq!(single.to_string(), "1.2.3.4");
+        assert_eq!(single.native_form().as_deref(), Some("1.2.3.4"));
+    }
+
+    #[test]
+    fn cidr_masks_network() {
+        let cidr = range("10.1.2.3/8");
+        assert_eq!(cidr.network(), "10.0.0.0".parse::<IpAddr>().unwrap());
+        assert_eq!(cidr.to_string(), "10.0.0.0/8");
+        assert!(cidr.contains_str("10.255.0.1"));
+        assert!(!cidr.contains_str("11.0.0.1"));
+        assert_eq!(cidr.native_form(), None);
+
+        let everything = range("0.0.0.0/0");
+        assert!(everything.contains_str("255.255.255.255"));
+        assert!(!everything.contains_str("::1"));
+    }
+
+    #[test]
+    fn ipv6_cidr() {
+        let cidr = range("2001:db8::/32");
+        assert!(cidr.contains_str("2001:db8:ffff::1"));
+        assert!(!cidr.contains_str("2001:db9::1"));
+        assert!(!cidr.contains_str("32.1.13.184"));
+        assert_eq!(cidr.to_string(), "2001:db8::/32");
+    }
+
+    #[test]
+    fn ipv4_mapped_is_normalized() {
+        let mapped = range("::ffff:1.2.3.4");
+        assert_eq!(mapped, range("1.2.3.4"));
+        assert!(range("1.2.3.0/24").contains_str("::ffff:1.2.3.200"));
+        assert_eq!(range("::ffff:1.2.0.0/112"), range("1.2.0.0/16"));
+        assert!(matches!(
+            "::ffff:1.2.0.0/95".parse::<IpRange>(),
+            Err(IpRangeError::InvalidPrefix(_))
+        ));
+    }
+
+    #[test]
+    fn wildcard() {
+        let wildcard = range("192.168.*.*");
+        assert_eq!(wildcard.prefix(), 16);
+        assert!(wildcard.contains_str("192.168.40.2"));
+        assert!(!wildcard.contains_str("192.169.0.1"));
+        assert_eq!(wildcard.to_string(), "192.168.*.*");
+        assert_eq!(wildcard.native_form().as_deref(), Some("192.168.*.*"));
+        assert!(range("*.*.*.*").contains_str("8.8.8.8"));
+        assert!(range("*.*.*.*").is_everything());
+        assert!(!wildcard.is_everything());
+    }
+
+    #[test]
+    fn equality_ignores_notation() {
+        use std::collections::HashSet;
+
+        assert_eq!(range("1.2.3.*"), range("1.2.3.0/24"));
+        assert_eq!(range("1.2.3.*"), range("1.2.3.77/24"));
+        assert_eq!(range("*.*.*.*"), range("0.0.0.0/0"));
+        assert_ne!(range("1.2.3.*"), range("1.2.3.0/25"));
+        assert_ne!(range("0.0.0.0/0"), range("::/0"));
+        let set: HashSet<IpRange> = ["1.2.*.*", "1.2.0.0/16", "1.2.9.9/16"]
+            .into_iter()
+            .map(range)
+            .collect();
+        assert_eq!(set.len(), 1);
+    }
+
+    #[test]
+    fn invalid_input() {
+        let invalid = |value: &str| value.parse::<IpRange>().unwrap_err();
+        assert!(matches!(
+            invalid("1.2.3.256"),
+            IpRangeError::InvalidAddress(_)
+        ));
+        assert!(matches!(invalid("1.2.3"), IpRangeError::InvalidAddress(_)));
+        assert!(matches!(invalid("nope/8"), IpRangeError::InvalidAddress(_)));
+        assert!(matches!(
+            invalid("1.2.3.4/33"),
+            IpRangeError::InvalidPrefix(_)
+        ));
+        assert!(matches!(invalid("::1/129"), IpRangeError::InvalidPrefix(_)));
+        assert!(matches!(
+            invalid("1.2.3.4/x"),
+            IpRangeError::InvalidPrefix(_)
+        ));
+        assert!(matches!(
+            invalid("1.*.3.4"),
+            IpRangeError::InvalidWildcard(_)
+        ));
+        assert!(matches!(invalid("1.2.*"), IpRangeError::InvalidWildcard(_)));
+        assert!(matches!(
+            invalid("300.2.*.*"),
+            IpRangeError::InvalidWildcard(_)
+        ));
+        assert!(matches!(invalid("::*"), IpRangeError::InvalidWildcard(_)));
+        assert!(!range("1.2.3.4").contains_str("not an ip"));
+    }
+}